@@ -0,0 +1,345 @@
+/// A character set that can be designated to one of G0 through G3
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Charset {
+    Ascii,
+    DecSpecialGraphics,
+    DecSupplemental,
+    DecTechnical,
+    Latin1Supplemental,
+    British,
+    Dutch,
+    Finnish,
+    French,
+    FrenchCanadian,
+    German,
+    Italian,
+    NorwegianDanish,
+    Portuguese,
+    Spanish,
+    Swedish,
+    Swiss,
+}
+
+impl Charset {
+    /// Find the character set for the intermediates and final character of a designation sequence
+    pub fn from_designation(intermediates: &[u8], c: char) -> Option<Charset> {
+        match intermediates {
+            // 94 character sets
+            [b'(', ..] | [b')', ..] | [b'*', ..] | [b'+', ..] => match (intermediates.get(1), c) {
+                (None, 'B') => Some(Charset::Ascii),
+                (None, '0') => Some(Charset::DecSpecialGraphics),
+                (None, '<') | (Some(b'%'), '5') => Some(Charset::DecSupplemental),
+                (None, '>') => Some(Charset::DecTechnical),
+                (None, 'A') => Some(Charset::British),
+                (None, '4') => Some(Charset::Dutch),
+                (None, 'C') | (None, '5') => Some(Charset::Finnish),
+                (None, 'R') | (None, 'f') => Some(Charset::French),
+                (None, 'Q') | (None, '9') => Some(Charset::FrenchCanadian),
+                (None, 'K') => Some(Charset::German),
+                (None, 'Y') => Some(Charset::Italian),
+                (None, 'E') | (None, '6') | (None, '`') => Some(Charset::NorwegianDanish),
+                (Some(b'%'), '6') => Some(Charset::Portuguese),
+                (None, 'Z') => Some(Charset::Spanish),
+                (None, 'H') | (None, '7') => Some(Charset::Swedish),
+                (None, '=') => Some(Charset::Swiss),
+                _ => None,
+            },
+            // 96 character sets
+            [b'-', ..] | [b'.', ..] | [b'/', ..] => match (intermediates.get(1), c) {
+                (None, 'A') => Some(Charset::Latin1Supplemental),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// National replacement character sets, which are only used when DECNRCM is set
+    ///
+    /// The British set is excluded, as the VT100 supported it without DECNRCM.
+    pub fn is_national(self) -> bool {
+        ! matches!(
+            self,
+            Charset::Ascii |
+            Charset::DecSpecialGraphics |
+            Charset::DecSupplemental |
+            Charset::DecTechnical |
+            Charset::Latin1Supplemental |
+            Charset::British
+        )
+    }
+
+    /// Translate a printed character using this character set
+    pub fn translate(self, c: char) -> char {
+        match self {
+            Charset::Ascii => c,
+            Charset::DecSpecialGraphics => match c {
+                '_' => '\u{00A0}',
+                '`' => '◆',
+                'a' => '▒',
+                'b' => '␉',
+                'c' => '␌',
+                'd' => '␍',
+                'e' => '␊',
+                'f' => '°',
+                'g' => '±',
+                'h' => '␤',
+                'i' => '␋',
+                'j' => '┘',
+                'k' => '┐',
+                'l' => '┌',
+                'm' => '└',
+                'n' => '┼',
+                'o' => '⎺',
+                'p' => '⎻',
+                'q' => '─',
+                'r' => '⎼',
+                's' => '⎽',
+                't' => '├',
+                'u' => '┤',
+                'v' => '┴',
+                'w' => '┬',
+                'x' => '│',
+                'y' => '≤',
+                'z' => '≥',
+                '{' => 'π',
+                '|' => '≠',
+                '}' => '£',
+                '~' => '·',
+                _ => c,
+            },
+            Charset::DecSupplemental => match c {
+                // Mostly the upper half of ISO Latin-1
+                '(' => '¤',
+                'W' => 'Œ',
+                ']' => 'Ÿ',
+                'w' => 'œ',
+                '}' => 'ÿ',
+                '!' ..= '~' => char::from(c as u8 + 0x80),
+                _ => c,
+            },
+            Charset::DecTechnical => match c {
+                '!' => '⎷',
+                '"' => '┌',
+                '#' => '─',
+                '$' => '⌠',
+                '%' => '⌡',
+                '&' => '│',
+                '\'' => '⎡',
+                '(' => '⎣',
+                ')' => '⎤',
+                '*' => '⎦',
+                '+' => '⎛',
+                ',' => '⎝',
+                '-' => '⎞',
+                '.' => '⎠',
+                '/' => '⎨',
+                '0' => '⎬',
+                '<' => '≤',
+                '=' => '≠',
+                '>' => '≥',
+                '?' => '∫',
+                '@' => '∴',
+                'A' => '∝',
+                'B' => '∞',
+                'C' => '÷',
+                'D' => 'Δ',
+                'E' => '∇',
+                'F' => 'Φ',
+                'G' => 'Γ',
+                'H' => '∼',
+                'I' => '≃',
+                'J' => 'Θ',
+                'K' => '×',
+                'L' => 'Λ',
+                'M' => '⇔',
+                'N' => '⇒',
+                'O' => '≡',
+                'P' => 'Π',
+                'Q' => 'Ψ',
+                'S' => 'Σ',
+                'V' => '√',
+                'W' => 'Ω',
+                'X' => 'Ξ',
+                'Y' => 'Υ',
+                'Z' => '⊂',
+                '[' => '⊃',
+                '\\' => '∩',
+                ']' => '∪',
+                '^' => '∧',
+                '_' => '∨',
+                '`' => '¬',
+                'a' => 'α',
+                'b' => 'β',
+                'c' => 'χ',
+                'd' => 'δ',
+                'e' => 'ε',
+                'f' => 'φ',
+                'g' => 'γ',
+                'h' => 'η',
+                'i' => 'ι',
+                'j' => 'θ',
+                'k' => 'κ',
+                'l' => 'λ',
+                'n' => 'ν',
+                'o' => '∂',
+                'p' => 'π',
+                'q' => 'ψ',
+                'r' => 'ρ',
+                's' => 'σ',
+                't' => 'τ',
+                'v' => 'ƒ',
+                'w' => 'ω',
+                'x' => 'ξ',
+                'y' => 'υ',
+                'z' => 'ζ',
+                '{' => '←',
+                '|' => '↑',
+                '}' => '→',
+                '~' => '↓',
+                _ => c,
+            },
+            Charset::Latin1Supplemental => match c {
+                ' ' ..= '\x7F' => char::from(c as u8 + 0x80),
+                _ => c,
+            },
+            Charset::British => match c {
+                '#' => '£',
+                _ => c,
+            },
+            Charset::Dutch => match c {
+                '#' => '£',
+                '@' => '¾',
+                '[' => 'ĳ',
+                '\\' => '½',
+                ']' => '|',
+                '{' => '¨',
+                '|' => 'ƒ',
+                '}' => '¼',
+                '~' => '´',
+                _ => c,
+            },
+            Charset::Finnish => match c {
+                '[' => 'Ä',
+                '\\' => 'Ö',
+                ']' => 'Å',
+                '^' => 'Ü',
+                '`' => 'é',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'å',
+                '~' => 'ü',
+                _ => c,
+            },
+            Charset::French => match c {
+                '#' => '£',
+                '@' => 'à',
+                '[' => '°',
+                '\\' => 'ç',
+                ']' => '§',
+                '{' => 'é',
+                '|' => 'ù',
+                '}' => 'è',
+                '~' => '¨',
+                _ => c,
+            },
+            Charset::FrenchCanadian => match c {
+                '@' => 'à',
+                '[' => 'â',
+                '\\' => 'ç',
+                ']' => 'ê',
+                '^' => 'î',
+                '`' => 'ô',
+                '{' => 'é',
+                '|' => 'ù',
+                '}' => 'è',
+                '~' => 'û',
+                _ => c,
+            },
+            Charset::German => match c {
+                '@' => '§',
+                '[' => 'Ä',
+                '\\' => 'Ö',
+                ']' => 'Ü',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'ü',
+                '~' => 'ß',
+                _ => c,
+            },
+            Charset::Italian => match c {
+                '#' => '£',
+                '@' => '§',
+                '[' => '°',
+                '\\' => 'ç',
+                ']' => 'é',
+                '`' => 'ù',
+                '{' => 'à',
+                '|' => 'ò',
+                '}' => 'è',
+                '~' => 'ì',
+                _ => c,
+            },
+            Charset::NorwegianDanish => match c {
+                '@' => 'Ä',
+                '[' => 'Æ',
+                '\\' => 'Ø',
+                ']' => 'Å',
+                '^' => 'Ü',
+                '`' => 'ä',
+                '{' => 'æ',
+                '|' => 'ø',
+                '}' => 'å',
+                '~' => 'ü',
+                _ => c,
+            },
+            Charset::Portuguese => match c {
+                '[' => 'Ã',
+                '\\' => 'Ç',
+                ']' => 'Õ',
+                '{' => 'ã',
+                '|' => 'ç',
+                '}' => 'õ',
+                _ => c,
+            },
+            Charset::Spanish => match c {
+                '#' => '£',
+                '@' => '§',
+                '[' => '¡',
+                '\\' => 'Ñ',
+                ']' => '¿',
+                '{' => '°',
+                '|' => 'ñ',
+                '}' => 'ç',
+                _ => c,
+            },
+            Charset::Swedish => match c {
+                '@' => 'É',
+                '[' => 'Ä',
+                '\\' => 'Ö',
+                ']' => 'Å',
+                '^' => 'Ü',
+                '`' => 'é',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'å',
+                '~' => 'ü',
+                _ => c,
+            },
+            Charset::Swiss => match c {
+                '#' => 'ù',
+                '@' => 'à',
+                '[' => 'é',
+                '\\' => 'ç',
+                ']' => 'ê',
+                '^' => 'î',
+                '_' => 'è',
+                '`' => 'ô',
+                '{' => 'ä',
+                '|' => 'ö',
+                '}' => 'ü',
+                '~' => 'û',
+                _ => c,
+            },
+        }
+    }
+}
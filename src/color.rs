@@ -6,6 +6,15 @@ pub enum Color {
 }
 
 impl Color {
+    /// Whether the color has a relative luminance below half
+    pub fn is_dark(&self) -> bool {
+        let rgb = self.as_rgb();
+        let r = (rgb >> 16) & 0xFF;
+        let g = (rgb >> 8) & 0xFF;
+        let b = rgb & 0xFF;
+        2126 * r + 7152 * g + 722 * b < 128 * 10000
+    }
+
     pub fn as_rgb(&self) -> u32 {
         let encode_rgb = |r: u8, g: u8, b: u8| -> u32 {
             0xFF00_0000 | (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b)
@@ -0,0 +1,63 @@
+/// How the terminal identifies itself in device attribute and version reports
+#[derive(Clone, Debug)]
+pub struct Identity {
+    /// Conformance level reported by primary DA, where 62 is a VT220
+    pub level: u16,
+    /// Extensions reported by primary DA, such as 9 for national replacement character sets
+    pub features: Vec<u16>,
+    /// Terminal type reported by secondary DA, where 1 is a VT220
+    pub terminal_type: u16,
+    /// Firmware version reported by secondary DA
+    pub version: u16,
+    /// Unit ID reported by tertiary DA
+    pub unit_id: u32,
+    /// Name and version reported by XTVERSION
+    pub name: String,
+}
+
+impl Identity {
+    /// Primary DA report, `CSI ? level ; features c`
+    pub fn primary(&self) -> String {
+        let mut report = format!("\x1B[?{}", self.level);
+        for feature in self.features.iter() {
+            report.push_str(&format!(";{}", feature));
+        }
+        report.push('c');
+        report
+    }
+
+    /// Secondary DA report, `CSI > type ; version ; 0 c`
+    pub fn secondary(&self) -> String {
+        format!("\x1B[>{};{};0c", self.terminal_type, self.version)
+    }
+
+    /// Tertiary DA report, `DCS ! | unit ID ST`
+    pub fn tertiary(&self) -> String {
+        format!("\x1BP!|{:08X}\x1B\\", self.unit_id)
+    }
+
+    /// XTVERSION report, `DCS > | name ST`
+    pub fn xtversion(&self) -> String {
+        format!("\x1BP>|{}\x1B\\", self.name)
+    }
+}
+
+impl Default for Identity {
+    fn default() -> Identity {
+        // Encode the crate version like xterm's patch number, so 0.5.0 is 500
+        let part = |version: &str| version.parse::<u16>().unwrap_or(0);
+        let version = part(env!("CARGO_PKG_VERSION_MAJOR")).saturating_mul(10000)
+            .saturating_add(part(env!("CARGO_PKG_VERSION_MINOR")).saturating_mul(100))
+            .saturating_add(part(env!("CARGO_PKG_VERSION_PATCH")));
+
+        Identity {
+            level: 62,
+            // National replacement character sets and ANSI color
+            features: vec![9, 22],
+            terminal_type: 1,
+            version,
+            unit_id: 0,
+            name: format!("ransid({})", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
@@ -0,0 +1,393 @@
+use modifiers::Modifiers;
+use State;
+
+/// A key pressed on the host keyboard
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// A key producing a character, after shift has been applied
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    /// A function key, from F1 to F24
+    F(u8),
+    /// A key on the numeric keypad, one of `0` to `9`, `.`, `+`, `-`, `*`, `/`, `=`, or `,`
+    Keypad(char),
+    KeypadEnter,
+}
+
+/// Apply the control modifier to a character, as in the VT220 keyboard
+fn control(c: char) -> char {
+    match c {
+        'a' ..= 'z' | 'A' ..= 'Z' => ((c as u8) & 0x1F) as char,
+        '@' | ' ' | '2' => '\x00',
+        '[' | '3' => '\x1B',
+        '\\' | '4' => '\x1C',
+        ']' | '5' => '\x1D',
+        '^' | '6' => '\x1E',
+        '_' | '7' | '/' => '\x1F',
+        '?' | '8' => '\x7F',
+        _ => c,
+    }
+}
+
+impl Key {
+    /// Encode the key press for the active keyboard modes
+    pub fn encode(self, modifiers: Modifiers, state: &State) -> Option<Vec<u8>> {
+        if let Some(code) = self.modify_other_keys(modifiers, state.modify_other_keys) {
+            let report = if state.format_other_keys {
+                format!("\x1B[{};{}u", code, modifiers.param())
+            } else {
+                format!("\x1B[27;{};{}~", modifiers.param(), code)
+            };
+            return Some(report.into_bytes());
+        }
+
+        // Keys that are sent as characters, which alt prefixes with ESC
+        let text = match self {
+            Key::Char(c) => Some(if modifiers.ctrl { control(c) } else { c }),
+            Key::Enter => Some('\r'),
+            Key::Tab if ! modifiers.shift => Some('\t'),
+            Key::Backspace => Some(if modifiers.ctrl { '\x08' } else { '\x7F' }),
+            Key::Escape => Some('\x1B'),
+            Key::Keypad(c) if ! state.keypad => Some(c),
+            Key::KeypadEnter if ! state.keypad => Some('\r'),
+            _ => None,
+        };
+        if let Some(c) = text {
+            let mut report = String::new();
+            if modifiers.alt {
+                report.push('\x1B');
+            }
+            report.push(c);
+            // Enter sends CR LF in newline mode
+            if state.newline && matches!(self, Key::Enter | Key::KeypadEnter) {
+                report.push('\n');
+            }
+            return Some(report.into_bytes());
+        }
+
+        let report = match self {
+            Key::Tab => "\x1B[Z".to_string(),
+            Key::Up | Key::Down | Key::Right | Key::Left | Key::Home | Key::End => {
+                let c = match self {
+                    Key::Up => 'A',
+                    Key::Down => 'B',
+                    Key::Right => 'C',
+                    Key::Left => 'D',
+                    Key::Home => 'H',
+                    _ => 'F',
+                };
+                if ! modifiers.is_empty() {
+                    format!("\x1B[1;{}{}", modifiers.param(), c)
+                } else if state.cursor_keys {
+                    format!("\x1BO{}", c)
+                } else {
+                    format!("\x1B[{}", c)
+                }
+            },
+            Key::Insert | Key::Delete | Key::PageUp | Key::PageDown => {
+                let code = match self {
+                    Key::Insert => 2,
+                    Key::Delete => 3,
+                    Key::PageUp => 5,
+                    _ => 6,
+                };
+                if modifiers.is_empty() {
+                    format!("\x1B[{}~", code)
+                } else {
+                    format!("\x1B[{};{}~", code, modifiers.param())
+                }
+            },
+            Key::F(n @ 1 ..= 24) => {
+                // F13 to F24 are sent as F1 to F12 with shift
+                let (n, modifiers) = if n > 12 {
+                    (n - 12, Modifiers { shift: true, ..modifiers })
+                } else {
+                    (n, modifiers)
+                };
+                match n {
+                    1 ..= 4 => {
+                        let c = (b'P' + n - 1) as char;
+                        if modifiers.is_empty() {
+                            format!("\x1BO{}", c)
+                        } else {
+                            format!("\x1B[1;{}{}", modifiers.param(), c)
+                        }
+                    },
+                    _ => {
+                        let code = match n {
+                            5 => 15,
+                            6 => 17,
+                            7 => 18,
+                            8 => 19,
+                            9 => 20,
+                            10 => 21,
+                            11 => 23,
+                            _ => 24,
+                        };
+                        if modifiers.is_empty() {
+                            format!("\x1B[{}~", code)
+                        } else {
+                            format!("\x1B[{};{}~", code, modifiers.param())
+                        }
+                    }
+                }
+            },
+            Key::Keypad(c) => {
+                // Application keypad mode
+                let code = match c {
+                    '0' ..= '9' => (b'p' + (c as u8 - b'0')) as char,
+                    '.' => 'n',
+                    '+' => 'k',
+                    '-' => 'm',
+                    '*' => 'j',
+                    '/' => 'o',
+                    '=' => 'X',
+                    ',' => 'l',
+                    _ => return None,
+                };
+                format!("\x1BO{}", code)
+            },
+            Key::KeypadEnter => "\x1BOM".to_string(),
+            _ => return None,
+        };
+
+        Some(report.into_bytes())
+    }
+
+    /// The key code to send for xterm's modifyOtherKeys, if it applies to this key
+    fn modify_other_keys(self, modifiers: Modifiers, level: u8) -> Option<u32> {
+        if level == 0 || modifiers.is_empty() {
+            return None;
+        }
+
+        let (code, modify) = match self {
+            Key::Char(c) => (c as u32, match level {
+                // Only keys that cannot be sent otherwise
+                1 => modifiers.ctrl && (control(c) == c || c.is_ascii_uppercase()),
+                // All keys with modifiers other than shift
+                _ => modifiers.ctrl || modifiers.alt || modifiers.meta,
+            }),
+            Key::Enter => (13, level > 1 || modifiers.ctrl || modifiers.shift),
+            Key::Tab => (9, level > 1 || modifiers.ctrl),
+            Key::Backspace => (127, level > 1),
+            Key::Escape => (27, level > 1 || modifiers.ctrl || modifiers.shift),
+            _ => return None,
+        };
+
+        if modify {
+            Some(code)
+        } else {
+            None
+        }
+    }
+}
+
+/// Flags of the kitty keyboard protocol
+pub const KITTY_DISAMBIGUATE: u8 = 1;
+pub const KITTY_EVENT_TYPES: u8 = 2;
+pub const KITTY_ALTERNATE_KEYS: u8 = 4;
+pub const KITTY_ALL_KEYS: u8 = 8;
+pub const KITTY_TEXT: u8 = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KeyEventKind {
+    Press,
+    Repeat,
+    Release,
+}
+
+/// A key event from the host, with the details used by the kitty keyboard protocol
+#[derive(Clone, Debug)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+    pub kind: KeyEventKind,
+    /// The character of the key without shift, like `1` for `!`, if it differs from `key`
+    pub unshifted_key: Option<char>,
+    /// The character of the key at the same position in the standard PC-101 layout
+    pub base_layout_key: Option<char>,
+    /// The text produced by the key, if it differs from `key`
+    pub text: Option<String>,
+}
+
+impl KeyEvent {
+    pub fn new(key: Key, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent {
+            key,
+            modifiers,
+            kind: KeyEventKind::Press,
+            unshifted_key: None,
+            base_layout_key: None,
+            text: None,
+        }
+    }
+
+    /// Encode the key event for the active keyboard modes
+    pub fn encode(&self, state: &State) -> Option<Vec<u8>> {
+        let flags = state.kitty_keyboard.last().copied().unwrap_or(0);
+        if flags == 0 {
+            return match self.kind {
+                KeyEventKind::Release => None,
+                _ => self.key.encode(self.modifiers, state),
+            };
+        }
+
+        let all_keys = flags & KITTY_ALL_KEYS != 0;
+        let kind = match self.kind {
+            KeyEventKind::Press => KeyEventKind::Press,
+            _ if flags & KITTY_EVENT_TYPES == 0 => match self.kind {
+                KeyEventKind::Release => return None,
+                _ => KeyEventKind::Press,
+            },
+            kind => kind,
+        };
+
+        // Lock modifiers are only reported when all keys are sent as escape codes
+        let mut modifiers = self.modifiers;
+        if ! all_keys {
+            modifiers.caps_lock = false;
+            modifiers.num_lock = false;
+        }
+        let plain = ! (modifiers.alt || modifiers.ctrl || modifiers.super_key || modifiers.hyper || modifiers.meta);
+        let unmodified = plain && ! modifiers.shift;
+
+        // Final character and key number
+        let (code, c) = match self.key {
+            Key::Char(c) => {
+                let unshifted = self.unshifted_key.unwrap_or_else(|| {
+                    if modifiers.shift {
+                        c.to_lowercase().next().unwrap_or(c)
+                    } else {
+                        c
+                    }
+                });
+                (unshifted as u32, 'u')
+            },
+            Key::Enter => (13, 'u'),
+            Key::Tab => (9, 'u'),
+            Key::Backspace => (127, 'u'),
+            Key::Escape => (27, 'u'),
+            Key::Up => (1, 'A'),
+            Key::Down => (1, 'B'),
+            Key::Right => (1, 'C'),
+            Key::Left => (1, 'D'),
+            Key::Home => (1, 'H'),
+            Key::End => (1, 'F'),
+            Key::Insert => (2, '~'),
+            Key::Delete => (3, '~'),
+            Key::PageUp => (5, '~'),
+            Key::PageDown => (6, '~'),
+            Key::F(1) => (1, 'P'),
+            Key::F(2) => (1, 'Q'),
+            Key::F(3) => (13, '~'),
+            Key::F(4) => (1, 'S'),
+            Key::F(n @ 5 ..= 12) => (match n {
+                5 => 15,
+                6 => 17,
+                7 => 18,
+                8 => 19,
+                9 => 20,
+                10 => 21,
+                11 => 23,
+                _ => 24,
+            }, '~'),
+            Key::F(n @ 13 ..= 24) => (57376 + u32::from(n) - 13, 'u'),
+            Key::F(_) => return None,
+            Key::Keypad(c) => (match c {
+                '0' ..= '9' => 57399 + (c as u32 - '0' as u32),
+                '.' => 57409,
+                '/' => 57410,
+                '*' => 57411,
+                '-' => 57412,
+                '+' => 57413,
+                '=' => 57415,
+                ',' => 57416,
+                _ => return None,
+            }, 'u'),
+            Key::KeypadEnter => (57414, 'u'),
+        };
+
+        // Keys that are still sent as in legacy mode, unless all keys are sent as escape codes
+        if ! all_keys && kind != KeyEventKind::Release {
+            let legacy = match self.key {
+                Key::Char(_) => plain,
+                Key::Enter | Key::Tab | Key::Backspace => unmodified,
+                Key::Escape | Key::Keypad(_) | Key::KeypadEnter => false,
+                _ => unmodified && kind == KeyEventKind::Press,
+            };
+            if legacy {
+                return self.key.encode(self.modifiers, state);
+            }
+        }
+
+        // Enter, Tab, and Backspace releases are only sent when all keys are sent as escape codes
+        if kind == KeyEventKind::Release && ! all_keys {
+            match self.key {
+                Key::Enter | Key::Tab | Key::Backspace => return None,
+                _ => (),
+            }
+        }
+
+        let mut report = format!("\x1B[{}", code);
+        if c == 'u' && flags & KITTY_ALTERNATE_KEYS != 0 {
+            if let Key::Char(key) = self.key {
+                let shifted = if modifiers.shift && key as u32 != code { Some(key) } else { None };
+                match (shifted, self.base_layout_key) {
+                    (Some(shifted), Some(base)) => report.push_str(&format!(":{}:{}", shifted as u32, base as u32)),
+                    (Some(shifted), None) => report.push_str(&format!(":{}", shifted as u32)),
+                    (None, Some(base)) => report.push_str(&format!("::{}", base as u32)),
+                    (None, None) => (),
+                }
+            }
+        }
+
+        // Associated text, only for keys that produce it
+        let text = if all_keys && flags & KITTY_TEXT != 0 && kind != KeyEventKind::Release {
+            match (&self.text, self.key) {
+                (Some(text), _) => Some(text.clone()),
+                (None, Key::Char(key)) if plain => Some(key.to_string()),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        let event = match kind {
+            KeyEventKind::Press => None,
+            KeyEventKind::Repeat => Some(2),
+            KeyEventKind::Release => Some(3),
+        };
+        let param = modifiers.kitty_param();
+        if param != 1 || event.is_some() || text.is_some() {
+            report.push_str(&format!(";{}", param));
+            if let Some(event) = event {
+                report.push_str(&format!(":{}", event));
+            }
+        }
+        if let Some(text) = text {
+            let codepoints: Vec<String> = text.chars().map(|c| (c as u32).to_string()).collect();
+            report.push_str(&format!(";{}", codepoints.join(":")));
+        }
+
+        // Keys with a letter as their final character omit the key number when it is 1
+        if code == 1 && c != '~' && c != 'u' && report == "\x1B[1" {
+            report.truncate(2);
+        }
+        report.push(c);
+
+        Some(report.into_bytes())
+    }
+}
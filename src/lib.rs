@@ -1,12 +1,31 @@
 #[macro_use]
 extern crate log;
+extern crate unicode_width;
 extern crate vte;
 
-use std::{char, cmp, str};
+use std::{char, cmp, mem, str};
 
+pub use charset::Charset;
 pub use color::Color;
+pub use identity::Identity;
+pub use key::{Key, KeyEvent, KeyEventKind};
+pub use mode::{Mode, ModeValue};
+pub use modifiers::Modifiers;
+pub use mouse::{MouseButton, MouseEvent, MouseEventKind};
+pub use screen::{Cell, Screen};
+pub use scrollback::Scrollback;
+pub use width::WidthPolicy;
 
+pub mod charset;
 pub mod color;
+pub mod identity;
+pub mod key;
+pub mod mode;
+pub mod modifiers;
+pub mod mouse;
+pub mod screen;
+pub mod scrollback;
+pub mod width;
 
 #[derive(Debug)]
 pub enum Event<'a> {
@@ -14,10 +33,19 @@ pub enum Event<'a> {
         x: usize,
         y: usize,
         c: char,
+        /// The whole grapheme cluster, starting with `c`
+        grapheme: &'a str,
+        wide: bool,
         bold: bool,
+        dim: bool,
         italic: bool,
-        underlined: bool,
+        underline: Underline,
+        underline_color: Option<Color>,
+        blink: bool,
+        rapid_blink: bool,
+        hidden: bool,
         strikethrough: bool,
+        overline: bool,
         color: Color
     },
     Input {
@@ -30,9 +58,9 @@ pub enum Event<'a> {
         h: usize,
         color: Color
     },
-    ScreenBuffer {
-        alternate: bool,
-        clear: bool,
+    Redraw,
+    FocusReporting {
+        enabled: bool,
     },
     Move {
         from_x: usize,
@@ -51,34 +79,192 @@ pub enum Event<'a> {
     }
 }
 
+/// Style of underline
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Underline {
+    None,
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
+/// Whether a column has a tab stop by default, which is every 8 columns
+fn default_tab(x: usize) -> bool {
+    x > 0 && x % 8 == 0
+}
+
+/// Parse the color of SGR 38, 48, or 58, either from subparameters or from the following parameters
+fn sgr_color<'a, I: Iterator<Item=&'a [u16]>>(param: &[u16], param_iter: &mut I) -> Option<Color> {
+    if param.len() > 1 {
+        // ITU T.416 subparameters, like 38:5:n or 38:2::r:g:b
+        match param[1] {
+            2 => {
+                //True color, where the color space identifier may be omitted
+                let rgb = if param.len() > 5 { &param[3..] } else { &param[2..] };
+                match *rgb {
+                    [r, g, b, ..] => Some(Color::TrueColor(r as u8, g as u8, b as u8)),
+                    _ => None,
+                }
+            },
+            5 => {
+                //256 color
+                param.get(2).map(|&color_value| Color::Ansi(color_value as u8))
+            },
+            _ => None,
+        }
+    } else {
+        let mut next = || param_iter.next().and_then(|param| param.first().copied()).unwrap_or(0);
+        match next() {
+            2 => {
+                //True color
+                let r = next();
+                let g = next();
+                let b = next();
+                Some(Color::TrueColor(r as u8, g as u8, b as u8))
+            },
+            5 => {
+                //256 color
+                let color_value = next();
+                Some(Color::Ansi(color_value as u8))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Cursor state saved by DECSC and restored by DECRC
+#[derive(Copy, Clone, Debug)]
+pub struct SavedCursor {
+    pub x: usize,
+    pub y: usize,
+    pub foreground: Color,
+    pub background: Color,
+    pub bold: bool,
+    pub dim: bool,
+    pub inverted: bool,
+    pub italic: bool,
+    pub underline: Underline,
+    pub underline_color: Option<Color>,
+    pub blink: bool,
+    pub rapid_blink: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+    pub overline: bool,
+    pub origin: bool,
+    pub autowrap: bool,
+    pub pending_wrap: bool,
+    pub g0: Charset,
+    pub g1: Charset,
+    pub g2: Charset,
+    pub g3: Charset,
+    pub gl: usize,
+}
+
+impl SavedCursor {
+    pub fn new(foreground: Color, background: Color) -> SavedCursor {
+        SavedCursor {
+            x: 0,
+            y: 0,
+            foreground,
+            background,
+            bold: false,
+            dim: false,
+            inverted: false,
+            italic: false,
+            underline: Underline::None,
+            underline_color: None,
+            blink: false,
+            rapid_blink: false,
+            hidden: false,
+            strikethrough: false,
+            overline: false,
+            origin: false,
+            autowrap: true,
+            pending_wrap: false,
+            g0: Charset::Ascii,
+            g1: Charset::DecSpecialGraphics,
+            g2: Charset::Ascii,
+            g3: Charset::Ascii,
+            gl: 0,
+        }
+    }
+}
+
 pub struct State {
     pub x: usize,
     pub y: usize,
-    pub save_x: usize,
-    pub save_y: usize,
+    pub save: SavedCursor,
     pub w: usize,
     pub h: usize,
     pub top_margin: usize,
     pub bottom_margin: usize,
-    pub g0: char,
-    pub g1: char,
+    pub left_margin: usize,
+    pub right_margin: usize,
+    /// Tab stops, one for each column
+    pub tabs: Vec<bool>,
+    pub g0: Charset,
+    pub g1: Charset,
+    pub g2: Charset,
+    pub g3: Charset,
+    pub gl: usize,
+    pub single_shift: Option<usize>,
+    pub nrcs: bool,
     pub foreground: Color,
     pub background: Color,
     pub foreground_default: Color,
     pub background_default: Color,
     pub bold: bool,
+    pub dim: bool,
     pub inverted: bool,
     pub italic: bool,
-    pub underlined: bool,
+    pub underline: Underline,
+    pub underline_color: Option<Color>,
+    pub blink: bool,
+    pub rapid_blink: bool,
+    pub hidden: bool,
     pub strikethrough: bool,
+    pub overline: bool,
     pub cursor: bool,
+    pub cursor_keys: bool,
+    pub keypad: bool,
+    pub kitty_keyboard: Vec<u8>,
+    pub inactive_kitty_keyboard: Vec<u8>,
+    pub modify_other_keys: u8,
+    /// Send modifyOtherKeys as `CSI code ; modifiers u`, like xterm's formatOtherKeys resource
+    pub format_other_keys: bool,
     pub redraw: bool,
     pub origin: bool,
     pub autowrap: bool,
+    /// The last column was printed, so the next character wraps to a new line
+    pub pending_wrap: bool,
+    /// DECLRMM, which allows DECSLRM to set the left and right margins
+    pub left_right_margins: bool,
+    pub insert: bool,
+    pub newline: bool,
+    /// Return to the left margin on every line feed, as versions before 0.6 did
+    pub implicit_carriage_return: bool,
+    pub mouse_x10: bool,
     pub mouse_vt200: bool,
     pub mouse_btn: bool,
+    pub mouse_any: bool,
+    pub mouse_utf8: bool,
     pub mouse_sgr: bool,
     pub mouse_rxvt: bool,
+    pub mouse_sgr_pixels: bool,
+    pub bracketed_paste: bool,
+    pub focus_reporting: bool,
+    pub alternate: bool,
+    pub last_print: Option<(usize, usize)>,
+    /// The last graphic character printed, which is repeated by REP
+    pub last_char: Option<char>,
+    pub width_policy: WidthPolicy,
+    pub identity: Identity,
+    pub screen: Screen,
+    pub inactive_screen: Screen,
+    pub inactive_save: SavedCursor,
+    pub scrollback: Scrollback,
 }
 
 impl State {
@@ -86,90 +272,450 @@ impl State {
         State {
             x: 0,
             y: 0,
-            save_x: 0,
-            save_y: 0,
+            save: SavedCursor::new(Color::Ansi(7), Color::Ansi(0)),
             w,
             h,
             top_margin: 0,
             bottom_margin: cmp::max(0, h as isize - 1) as usize,
-            g0: 'B',
-            g1: '0',
+            left_margin: 0,
+            right_margin: cmp::max(0, w as isize - 1) as usize,
+            tabs: (0..w).map(default_tab).collect(),
+            g0: Charset::Ascii,
+            g1: Charset::DecSpecialGraphics,
+            g2: Charset::Ascii,
+            g3: Charset::Ascii,
+            gl: 0,
+            single_shift: None,
+            nrcs: false,
             foreground: Color::Ansi(7),
             background: Color::Ansi(0),
             foreground_default: Color::Ansi(7),
             background_default: Color::Ansi(0),
             bold: false,
+            dim: false,
             inverted: false,
             italic: false,
-            underlined: false,
+            underline: Underline::None,
+            underline_color: None,
+            blink: false,
+            rapid_blink: false,
+            hidden: false,
             strikethrough: false,
+            overline: false,
             cursor: true,
+            cursor_keys: false,
+            keypad: false,
+            kitty_keyboard: Vec::new(),
+            inactive_kitty_keyboard: Vec::new(),
+            modify_other_keys: 0,
+            format_other_keys: false,
             redraw: true,
             origin: false,
             autowrap: true,
+            pending_wrap: false,
+            left_right_margins: false,
+            insert: false,
+            newline: false,
+            implicit_carriage_return: false,
+            mouse_x10: false,
             mouse_vt200: false,
             mouse_btn: false,
+            mouse_any: false,
+            mouse_utf8: false,
             mouse_sgr: false,
             mouse_rxvt: false,
+            mouse_sgr_pixels: false,
+            bracketed_paste: false,
+            focus_reporting: false,
+            alternate: false,
+            last_print: None,
+            last_char: None,
+            width_policy: WidthPolicy::default(),
+            identity: Identity::default(),
+            screen: Screen::new(w, h),
+            inactive_screen: Screen::new(w, h),
+            inactive_save: SavedCursor::new(Color::Ansi(7), Color::Ansi(0)),
+            scrollback: Scrollback::new(1000),
+        }
+    }
+
+    /// Update the screen with an event, then pass it on to the callback
+    fn emit<F: FnMut(Event)>(&mut self, event: Event, callback: &mut F) {
+        self.screen.event(&event);
+        callback(event);
+    }
+
+    fn save_cursor(&mut self) {
+        self.save = SavedCursor {
+            x: self.x,
+            y: self.y,
+            foreground: self.foreground,
+            background: self.background,
+            bold: self.bold,
+            dim: self.dim,
+            inverted: self.inverted,
+            italic: self.italic,
+            underline: self.underline,
+            underline_color: self.underline_color,
+            blink: self.blink,
+            rapid_blink: self.rapid_blink,
+            hidden: self.hidden,
+            strikethrough: self.strikethrough,
+            overline: self.overline,
+            origin: self.origin,
+            autowrap: self.autowrap,
+            pending_wrap: self.pending_wrap,
+            g0: self.g0,
+            g1: self.g1,
+            g2: self.g2,
+            g3: self.g3,
+            gl: self.gl,
+        };
+    }
+
+    fn restore_cursor(&mut self) {
+        let save = self.save;
+        self.x = cmp::min(save.x, self.w.saturating_sub(1));
+        self.y = cmp::min(save.y, self.h.saturating_sub(1));
+        self.foreground = save.foreground;
+        self.background = save.background;
+        self.bold = save.bold;
+        self.dim = save.dim;
+        self.inverted = save.inverted;
+        self.italic = save.italic;
+        self.underline = save.underline;
+        self.underline_color = save.underline_color;
+        self.blink = save.blink;
+        self.rapid_blink = save.rapid_blink;
+        self.hidden = save.hidden;
+        self.strikethrough = save.strikethrough;
+        self.overline = save.overline;
+        self.origin = save.origin;
+        self.autowrap = save.autowrap;
+        self.pending_wrap = save.pending_wrap && self.x == save.x;
+        self.g0 = save.g0;
+        self.g1 = save.g1;
+        self.g2 = save.g2;
+        self.g3 = save.g3;
+        self.gl = save.gl;
+        self.single_shift = None;
+    }
+
+    /// Reset all graphic rendition attributes
+    fn reset_attributes(&mut self) {
+        self.foreground = self.foreground_default;
+        self.background = self.background_default;
+        self.bold = false;
+        self.dim = false;
+        self.inverted = false;
+        self.italic = false;
+        self.underline = Underline::None;
+        self.underline_color = None;
+        self.blink = false;
+        self.rapid_blink = false;
+        self.hidden = false;
+        self.strikethrough = false;
+        self.overline = false;
+    }
+
+    fn charset(&self, g: usize) -> Charset {
+        match g {
+            0 => self.g0,
+            1 => self.g1,
+            2 => self.g2,
+            _ => self.g3,
+        }
+    }
+
+    /// Switch between the main and alternate screen, each with its own saved cursor
+    fn switch_screen<F: FnMut(Event)>(&mut self, alternate: bool, callback: &mut F) {
+        if self.alternate != alternate {
+            mem::swap(&mut self.screen, &mut self.inactive_screen);
+            mem::swap(&mut self.save, &mut self.inactive_save);
+            mem::swap(&mut self.kitty_keyboard, &mut self.inactive_kitty_keyboard);
+            self.alternate = alternate;
+
+            // The inactive screen is not resized by DECCOLM
+            self.screen.resize(self.w, self.h);
+
+            self.redraw = true;
+            self.emit(Event::Redraw, callback);
+        }
+    }
+
+    fn set_focus_reporting<F: FnMut(Event)>(&mut self, enabled: bool, callback: &mut F) {
+        if self.focus_reporting != enabled {
+            self.focus_reporting = enabled;
+            self.emit(Event::FocusReporting { enabled }, callback);
+        }
+    }
+
+    /// Set or reset a mode, as done by SM/RM and DECSET/DECRST
+    pub fn set_mode<F: FnMut(Event)>(&mut self, mode: Mode, value: bool, callback: &mut F) {
+        match mode {
+            Mode::Insert => self.insert = value,
+            Mode::Newline => self.newline = value,
+            Mode::CursorKeys => self.cursor_keys = value,
+            Mode::Column => {
+                self.x = 0;
+                self.y = 0;
+                self.top_margin = 0;
+                self.bottom_margin = cmp::max(0, self.h as isize - 1) as usize;
+
+                self.w = if value { 132 } else { 80 };
+                self.pending_wrap = false;
+                self.left_margin = 0;
+                self.right_margin = self.w - 1;
+                self.resize_tabs();
+                //Resize screen
+                self.emit(Event::Resize {
+                    w: self.w,
+                    h: self.h
+                }, callback);
+
+                // Clear screen
+                self.emit(Event::Rect {
+                    x: 0,
+                    y: 0,
+                    w: self.w,
+                    h: self.h,
+                    color: self.background
+                }, callback);
+            },
+            Mode::Origin => {
+                self.origin = value;
+                self.pending_wrap = false;
+                self.x = if value { self.left_margin } else { 0 };
+                self.y = if value { self.top_margin } else { 0 };
+            },
+            Mode::Autowrap => self.autowrap = value,
+            Mode::MouseX10 => self.mouse_x10 = value,
+            Mode::ShowCursor => self.cursor = value,
+            Mode::Nrcs => self.nrcs = value,
+            Mode::AlternateScreenLegacy => self.switch_screen(value, callback),
+            Mode::MouseVt200 => self.mouse_vt200 = value,
+            Mode::MouseBtn => self.mouse_btn = value,
+            Mode::MouseAny => self.mouse_any = value,
+            Mode::FocusReporting => self.set_focus_reporting(value, callback),
+            Mode::MouseUtf8 => self.mouse_utf8 = value,
+            Mode::MouseSgr => self.mouse_sgr = value,
+            Mode::MouseRxvt => self.mouse_rxvt = value,
+            Mode::MouseSgrPixels => self.mouse_sgr_pixels = value,
+            Mode::AlternateScreen => {
+                // Clear the alternate screen before leaving it
+                if ! value && self.alternate {
+                    self.clear_screen(callback);
+                }
+                self.switch_screen(value, callback);
+            },
+            Mode::SaveCursor => if value {
+                self.save_cursor();
+            } else {
+                self.restore_cursor();
+            },
+            Mode::AlternateScreenSaveCursor => if value {
+                // Save the cursor of the main screen, then switch to a cleared alternate screen
+                self.save_cursor();
+                self.switch_screen(true, callback);
+                self.clear_screen(callback);
+            } else {
+                // Switch to the main screen, then restore its cursor
+                self.switch_screen(false, callback);
+                self.restore_cursor();
+            },
+            Mode::LeftRightMargins => {
+                self.left_right_margins = value;
+                // Leaving the mode resets the margins to the full width
+                if ! value {
+                    self.left_margin = 0;
+                    self.right_margin = self.w.saturating_sub(1);
+                }
+            },
+            Mode::BracketedPaste => self.bracketed_paste = value,
+            Mode::KeyboardAction | Mode::SendReceive | Mode::Ansi | Mode::SmoothScroll | Mode::ReverseVideo => {
+                debug!("Permanent mode {:?} cannot be changed", mode);
+            }
+        }
+    }
+
+    /// Get the value of a mode, as reported by DECRPM
+    pub fn mode(&self, mode: Mode) -> ModeValue {
+        match mode {
+            Mode::KeyboardAction => ModeValue::PermanentlyReset,
+            Mode::Insert => self.insert.into(),
+            Mode::SendReceive => ModeValue::PermanentlySet,
+            Mode::Newline => self.newline.into(),
+            Mode::CursorKeys => self.cursor_keys.into(),
+            Mode::Ansi => ModeValue::PermanentlySet,
+            Mode::Column => (self.w == 132).into(),
+            Mode::SmoothScroll => ModeValue::PermanentlyReset,
+            Mode::ReverseVideo => ModeValue::PermanentlyReset,
+            Mode::Origin => self.origin.into(),
+            Mode::Autowrap => self.autowrap.into(),
+            Mode::MouseX10 => self.mouse_x10.into(),
+            Mode::ShowCursor => self.cursor.into(),
+            Mode::Nrcs => self.nrcs.into(),
+            Mode::LeftRightMargins => self.left_right_margins.into(),
+            Mode::AlternateScreenLegacy | Mode::AlternateScreen | Mode::AlternateScreenSaveCursor => self.alternate.into(),
+            Mode::MouseVt200 => self.mouse_vt200.into(),
+            Mode::MouseBtn => self.mouse_btn.into(),
+            Mode::MouseAny => self.mouse_any.into(),
+            Mode::FocusReporting => self.focus_reporting.into(),
+            Mode::MouseUtf8 => self.mouse_utf8.into(),
+            Mode::MouseSgr => self.mouse_sgr.into(),
+            Mode::MouseRxvt => self.mouse_rxvt.into(),
+            Mode::MouseSgrPixels => self.mouse_sgr_pixels.into(),
+            Mode::SaveCursor => ModeValue::Reset,
+            Mode::BracketedPaste => self.bracketed_paste.into(),
+        }
+    }
+
+    /// Reset the tab stops to every 8 columns
+    fn reset_tabs(&mut self) {
+        self.tabs = (0..self.w).map(default_tab).collect();
+    }
+
+    /// Resize the tab stops to the width, with default tab stops in new columns
+    fn resize_tabs(&mut self) {
+        let len = self.tabs.len();
+        self.tabs.truncate(self.w);
+        self.tabs.extend((len..self.w).map(default_tab));
+    }
+
+    /// Column of the next tab stop, or the last column if there is none
+    fn next_tab(&self, x: usize) -> usize {
+        let last = self.w.saturating_sub(1);
+        (x + 1..self.w).find(|&x| self.tabs[x]).unwrap_or(last)
+    }
+
+    /// Column of the previous tab stop, or the first column if there is none
+    fn previous_tab(&self, x: usize) -> usize {
+        (0..cmp::min(x, self.w)).rev().find(|&x| self.tabs[x]).unwrap_or(0)
+    }
+
+    /// Cursor position for CPR, starting at 1 and relative to the margins in origin mode
+    fn report_position(&self) -> (usize, usize) {
+        if self.origin {
+            (self.x.saturating_sub(self.left_margin) + 1, self.y.saturating_sub(self.top_margin) + 1)
+        } else {
+            (self.x + 1, self.y + 1)
+        }
+    }
+
+    /// Whether the cursor is between the left and right margins
+    fn in_left_right_margins(&self) -> bool {
+        self.x >= self.left_margin && self.x <= self.right_margin
+    }
+
+    fn clear_screen<F: FnMut(Event)>(&mut self, callback: &mut F) {
+        self.emit(Event::Rect {
+            x: 0,
+            y: 0,
+            w: self.w,
+            h: self.h,
+            color: self.background
+        }, callback);
+    }
+
+    /// Clear the other half of any wide character that would be partially overwritten
+    fn clear_wide<F: FnMut(Event)>(&mut self, x: usize, w: usize, callback: &mut F) {
+        let y = self.y;
+        let color = self.background;
+
+        // Overwriting the right half of a wide character
+        if x > 0 && self.screen.cell(x - 1, y).is_some_and(|cell| cell.wide) {
+            self.emit(Event::Rect { x: x - 1, y, w: 1, h: 1, color }, callback);
+        }
+
+        // Overwriting the left half of a wide character
+        let last = x + w - 1;
+        if self.screen.cell(last, y).is_some_and(|cell| cell.wide) && last + 1 < self.w {
+            self.emit(Event::Rect { x: last + 1, y, w: 1, h: 1, color }, callback);
         }
     }
 
-    fn block<F: FnMut(Event)>(&self, c: char, callback: &mut F) {
-        callback(Event::Rect {
+    fn block<F: FnMut(Event)>(&mut self, c: char, wide: bool, callback: &mut F) {
+        let w = if wide { 2 } else { 1 };
+        self.clear_wide(self.x, w, callback);
+
+        self.emit(Event::Rect {
             x: self.x,
             y: self.y,
-            w: 1,
+            w,
             h: 1,
             color: if self.inverted { self.foreground } else { self.background }
-        });
-        callback(Event::Char {
+        }, callback);
+        let mut buf = [0; 4];
+        self.emit(Event::Char {
             x: self.x,
             y: self.y,
             c,
+            grapheme: c.encode_utf8(&mut buf),
+            wide,
             bold: self.bold,
+            dim: self.dim,
             italic: self.italic,
-            underlined: self.underlined,
+            underline: self.underline,
+            underline_color: self.underline_color,
+            blink: self.blink,
+            rapid_blink: self.rapid_blink,
+            hidden: self.hidden,
             strikethrough: self.strikethrough,
+            overline: self.overline,
             color: if self.inverted { self.background } else { self.foreground }
-        });
+        }, callback);
     }
 
-    fn scroll<F: FnMut(Event)>(&self, rows: usize, callback: &mut F) {
-        //TODO: Use min and max to ensure correct behavior
-        callback(Event::Move {
-            from_x: 0,
+    fn scroll<F: FnMut(Event)>(&mut self, rows: usize, callback: &mut F) {
+        // Scrolling by more than the region clears it
+        let rows = cmp::min(rows, (self.bottom_margin + 1) - self.top_margin);
+
+        // Like xterm, only save full lines scrolled off the top of the main screen
+        let full_width = self.left_margin == 0 && self.right_margin + 1 >= self.w;
+        if self.top_margin == 0 && full_width && ! self.alternate {
+            for y in 0..rows {
+                if let Some(row) = self.screen.row(y) {
+                    self.scrollback.push(row.to_vec());
+                }
+            }
+        }
+
+        self.emit(Event::Move {
+            from_x: self.left_margin,
             from_y: self.top_margin + rows,
-            to_x: 0,
+            to_x: self.left_margin,
             to_y: self.top_margin,
-            w: self.w,
-            h: (self.bottom_margin + 1) - rows,
-        });
-        callback(Event::Rect {
-            x: 0,
+            w: (self.right_margin + 1) - self.left_margin,
+            h: (self.bottom_margin + 1) - self.top_margin - rows,
+        }, callback);
+        self.emit(Event::Rect {
+            x: self.left_margin,
             y: (self.bottom_margin + 1) - rows,
-            w: self.w,
+            w: (self.right_margin + 1) - self.left_margin,
             h: rows,
             color: self.background,
-        });
+        }, callback);
     }
 
-    fn reverse_scroll<F: FnMut(Event)>(&self, rows: usize, callback: &mut F) {
-        //TODO: Use min and max to ensure correct behavior
-        callback(Event::Move {
-            from_x: 0,
+    fn reverse_scroll<F: FnMut(Event)>(&mut self, rows: usize, callback: &mut F) {
+        // Scrolling by more than the region clears it
+        let rows = cmp::min(rows, (self.bottom_margin + 1) - self.top_margin);
+
+        self.emit(Event::Move {
+            from_x: self.left_margin,
             from_y: self.top_margin,
-            to_x: 0,
+            to_x: self.left_margin,
             to_y: self.top_margin + rows,
-            w: self.w,
-            h: (self.bottom_margin + 1) - rows,
-        });
-        callback(Event::Rect {
-            x: 0,
+            w: (self.right_margin + 1) - self.left_margin,
+            h: (self.bottom_margin + 1) - self.top_margin - rows,
+        }, callback);
+        self.emit(Event::Rect {
+            x: self.left_margin,
             y: self.top_margin,
-            w: self.w,
+            w: (self.right_margin + 1) - self.left_margin,
             h: rows,
             color: self.background,
-        });
+        }, callback);
     }
 
     fn fix_cursor<F: FnMut(Event)>(&mut self, callback: &mut F) {
@@ -177,12 +723,7 @@ impl State {
         let h = cmp::min(self.h, self.bottom_margin + 1);
 
         if self.x >= w {
-            if self.autowrap {
-                self.x = 0;
-                self.y += 1;
-            } else {
-                self.x = w.saturating_sub(1);
-            }
+            self.x = w.saturating_sub(1);
         }
 
         if self.y + 1 > h {
@@ -193,33 +734,189 @@ impl State {
     }
 
     pub fn print<F: FnMut(Event)>(&mut self, c: char, callback: &mut F) {
+        // A single shift only applies to the next character
+        let g = self.single_shift.take().unwrap_or(self.gl);
+        let charset = self.charset(g);
+        let c = if charset.is_national() && ! self.nrcs {
+            c
+        } else {
+            charset.translate(c)
+        };
+
+        if self.combine(c, callback) {
+            return;
+        }
+
+        self.print_char(c, callback);
+    }
+
+    /// Print a character after character set translation
+    fn print_char<F: FnMut(Event)>(&mut self, c: char, callback: &mut F) {
+        let width = self.width_policy.width(c);
+        if width == 0 {
+            debug!("Zero width character {:?}", c);
+            return;
+        }
+
+        let (left, right) = self.wrap_margins();
+
+        // Wrap after the last column was printed, or early instead of splitting a wide character across the right margin
+        if self.autowrap && (self.pending_wrap || (self.x > left && self.x + width > right)) {
+            self.x = left;
+            self.y += 1;
+        }
+        if self.x + width > right {
+            self.x = right.saturating_sub(width);
+        }
+        self.pending_wrap = false;
+
         self.fix_cursor(callback);
-        self.block(c, callback);
-        self.x += 1;
+
+        // Insert mode shifts the rest of the line right
+        if self.insert && self.x + width < right {
+            self.emit(Event::Move {
+                from_x: self.x,
+                from_y: self.y,
+                to_x: self.x + width,
+                to_y: self.y,
+                w: right - (self.x + width),
+                h: 1,
+            }, callback);
+        }
+
+        self.block(c, width > 1, callback);
+        self.last_print = Some((self.x, self.y));
+        self.last_char = Some(c);
+
+        // Stay on the last column until the next character is printed
+        if self.x + width >= right {
+            self.x = right - 1;
+            self.pending_wrap = self.autowrap;
+        } else {
+            self.x += width;
+        }
+    }
+
+    /// Columns that printing wraps between, which are the left and right margins unless the cursor is right of them
+    fn wrap_margins(&self) -> (usize, usize) {
+        if self.x <= self.right_margin {
+            (self.left_margin, self.right_margin + 1)
+        } else {
+            (0, self.w)
+        }
+    }
+
+    /// Attach a character to the grapheme cluster of the previously printed cell
+    fn combine<F: FnMut(Event)>(&mut self, c: char, callback: &mut F) -> bool {
+        let (x, y) = match self.last_print {
+            Some(last_print) => last_print,
+            None => return false,
+        };
+
+        let mut cell = match self.screen.cell(x, y) {
+            Some(cell) => cell.clone(),
+            None => return false,
+        };
+
+        let previous = cell.combining.chars().last().unwrap_or(cell.c);
+        let attach = match c {
+            // Combining marks, zero width joiners, and variation selectors
+            _ if self.width_policy.width(c) == 0 => true,
+            // Emoji modifiers following an emoji
+            '\u{1F3FB}' ..= '\u{1F3FF}' => width::is_pictographic(previous),
+            // Pairs of regional indicators
+            '\u{1F1E6}' ..= '\u{1F1FF}' => cell.combining.is_empty() && ('\u{1F1E6}' ..= '\u{1F1FF}').contains(&cell.c),
+            // Emoji following a zero width joiner
+            _ => previous == '\u{200D}' && width::is_pictographic(c),
+        };
+        if ! attach {
+            return false;
+        }
+
+        // Limit the length of clusters, which are otherwise unbounded
+        if cell.combining.chars().count() < 16 {
+            cell.combining.push(c);
+        }
+
+        // Emoji presentation widens a narrow character, if there is room for it
+        if c == '\u{FE0F}' && self.width_policy.vs16_wide && ! cell.wide && ! self.pending_wrap && self.x == x + 1 {
+            self.clear_wide(self.x, 1, callback);
+            self.emit(Event::Rect {
+                x: self.x,
+                y,
+                w: 1,
+                h: 1,
+                color: cell.background
+            }, callback);
+            cell.wide = true;
+            if self.x + 1 >= self.wrap_margins().1 {
+                self.pending_wrap = self.autowrap;
+            } else {
+                self.x += 1;
+            }
+        }
+
+        let mut grapheme = String::with_capacity(cell.c.len_utf8() + cell.combining.len());
+        grapheme.push(cell.c);
+        grapheme.push_str(&cell.combining);
+        self.emit(Event::Char {
+            x,
+            y,
+            c: cell.c,
+            grapheme: &grapheme,
+            wide: cell.wide,
+            bold: cell.bold,
+            dim: cell.dim,
+            italic: cell.italic,
+            underline: cell.underline,
+            underline_color: cell.underline_color,
+            blink: cell.blink,
+            rapid_blink: cell.rapid_blink,
+            hidden: cell.hidden,
+            strikethrough: cell.strikethrough,
+            overline: cell.overline,
+            color: cell.foreground
+        }, callback);
+
+        true
     }
 
     pub fn execute<F: FnMut(Event)>(&mut self, c: char, callback: &mut F) {
-        // Fix for vt100 wrapping behavior: http://invisible-island.net/xterm/xterm.faq.html#vt100_wrapping
-        //let xenl = self.x + 1 >= self.w;
-        let xenl = false;
+        self.last_print = None;
 
         match c {
             '\x07' => {
                 debug!("BELL not implemented");
             },
             '\x08' => { // Backspace
-                self.x = cmp::max(0, self.x as i64 - 1) as usize;
+                self.pending_wrap = false;
+                // Stop at the left margin, like CUB
+                if self.x != self.left_margin {
+                    self.x = self.x.saturating_sub(1);
+                }
             },
-            '\x09' => if ! xenl { // Tab
-                self.x = cmp::max(0, cmp::min(self.w as i64 - 1, ((self.x as i64 / 8) + 1) * 8)) as usize;
+            '\x09' => { // Tab
+                self.pending_wrap = false;
+                self.x = self.next_tab(self.x);
             },
-            '\x0A' => if ! xenl { // Newline
-                self.x = 0;
+            '\x0A' | '\x0B' | '\x0C' => { // LF (Line Feed), VT (Vertical Tab), FF (Form Feed)
+                self.pending_wrap = false;
+                // Only return to the left margin in newline mode
+                if self.newline || self.implicit_carriage_return {
+                    self.x = if self.x >= self.left_margin { self.left_margin } else { 0 };
+                }
                 self.y += 1;
                 self.fix_cursor(callback);
             },
-            '\x0D' => if ! xenl { // Carriage Return
-                self.x = 0;
+            '\x0D' => { // Carriage Return
+                self.pending_wrap = false;
+                self.x = if self.x >= self.left_margin { self.left_margin } else { 0 };
+            },
+            '\x0E' => { // SO (Shift Out), LS1 (Locking Shift 1)
+                self.gl = 1;
+            },
+            '\x0F' => { // SI (Shift In), LS0 (Locking Shift 0)
+                self.gl = 0;
             },
             _ => {
                 debug!("Unknown execute {:?}", c);
@@ -227,9 +924,36 @@ impl State {
         }
     }
 
-    pub fn csi<F: FnMut(Event)>(&mut self, c: char, params: &[i64], _intermediates: &[u8], callback: &mut F) {
+    /// Handle a CSI sequence with parameters that have no subparameters
+    pub fn csi<F: FnMut(Event)>(&mut self, c: char, params: &[i64], intermediates: &[u8], callback: &mut F) {
+        let mut values = [[0; 1]; 32];
+        for (value, &param) in values.iter_mut().zip(params.iter()) {
+            value[0] = param.clamp(0, i64::from(u16::MAX)) as u16;
+        }
+
+        let mut subparams: [&[u16]; 32] = [&[]; 32];
+        for (subparam, value) in subparams.iter_mut().zip(values.iter()) {
+            *subparam = value;
+        }
+
+        let len = cmp::min(params.len(), subparams.len());
+        self.csi_subparams(c, &subparams[..len], intermediates, callback);
+    }
+
+    /// Handle a CSI sequence, where each parameter is a list of its colon separated subparameters
+    pub fn csi_subparams<F: FnMut(Event)>(&mut self, c: char, subparams: &[&[u16]], intermediates: &[u8], callback: &mut F) {
+        self.last_print = None;
+
+        // Most sequences only use the first value of each parameter
+        let mut values = [0; 32];
+        for (value, param) in values.iter_mut().zip(subparams.iter()) {
+            *value = i64::from(param.first().copied().unwrap_or(0));
+        }
+        let params = &values[..cmp::min(subparams.len(), values.len())];
+
         match c {
             'A' => { // CUU (Cursor Up)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
                 if self.y < self.top_margin {
                     self.y = cmp::max(0, self.y as i64 - cmp::max(1, param)) as usize;
@@ -238,6 +962,7 @@ impl State {
                 }
             },
             'B' => { // CUD (Cursor Down)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
                 if self.y > self.bottom_margin {
                     self.y = cmp::max(0, cmp::min(self.h as i64 - 1, self.y as i64 + cmp::max(1, param))) as usize;
@@ -246,29 +971,51 @@ impl State {
                 }
             },
             'C' => { // CUF (Cursor Forward/Right)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
-                self.x = cmp::max(0, cmp::min(self.w as i64 - 1, self.x as i64 + cmp::max(1, param))) as usize;
+                if self.x > self.right_margin {
+                    self.x = cmp::max(0, cmp::min(self.w as i64 - 1, self.x as i64 + cmp::max(1, param))) as usize;
+                } else {
+                    self.x = cmp::max(0, cmp::min(self.right_margin as i64, self.x as i64 + cmp::max(1, param))) as usize;
+                }
             },
             'D' => { // CUB (Cursor Back/Left)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
-                self.x = cmp::max(0, self.x as i64 - cmp::max(1, param)) as usize;
+                if self.x < self.left_margin {
+                    self.x = cmp::max(0, self.x as i64 - cmp::max(1, param)) as usize;
+                } else {
+                    self.x = cmp::max(self.left_margin as i64, self.x as i64 - cmp::max(1, param)) as usize;
+                }
             },
             'E' => { // CNL (Cursor Next Line)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
-                self.x = 0;
+                self.x = if self.x >= self.left_margin { self.left_margin } else { 0 };
                 self.y += cmp::min(self.h.saturating_sub(self.y + 1), cmp::max(1, param) as usize);
             },
             'F' => { // CPL (Cursor Previous Line)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
-                self.x = 0;
+                self.x = if self.x >= self.left_margin { self.left_margin } else { 0 };
                 self.y -= cmp::min(self.y, cmp::max(1, param) as usize);
             },
             'G' => { // CHA (Cursor Horizontal Absolute)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
                 let col = cmp::max(1, param);
-                self.x = cmp::max(0, cmp::min(self.w as i64 - 1, col - 1)) as usize;
+
+                let (left, right) = if self.origin {
+                    (self.left_margin, self.right_margin + 1)
+                } else {
+                    (0, self.w)
+                };
+
+                self.x = cmp::max(0, cmp::min(right as i64 - 1, col + left as i64 - 1)) as usize;
             },
             'H' | 'f' => { // H = CUP (Cursor Position); f = HVP (Horizontal Vertical Position)
+                self.pending_wrap = false;
+
                 {
                     let param = params.first().copied().unwrap_or(1);
                     let row = cmp::max(1, param);
@@ -285,51 +1032,66 @@ impl State {
                 {
                     let param = params.get(1).copied().unwrap_or(1);
                     let col = cmp::max(1, param);
-                    self.x = cmp::max(0, cmp::min(self.w as i64 - 1, col - 1)) as usize;
+
+                    let (left, right) = if self.origin {
+                        (self.left_margin, self.right_margin + 1)
+                    } else {
+                        (0, self.w)
+                    };
+
+                    self.x = cmp::max(0, cmp::min(right as i64 - 1, col + left as i64 - 1)) as usize;
+                }
+            },
+            'I' => { // CHT (Cursor Horizontal Forward Tabulation)
+                self.pending_wrap = false;
+                let param = params.first().copied().unwrap_or(1);
+                for _ in 0..cmp::max(1, param) {
+                    self.x = self.next_tab(self.x);
                 }
             },
             'J' => { // ED (Erase in Display)
+                self.pending_wrap = false;
                 self.fix_cursor(callback);
 
                 let param = params.first().copied().unwrap_or(0);
                 match param {
                     0 => {
                         // Clear current row from cursor
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: self.x,
                             y: self.y,
                             w: self.w - self.x,
                             h: 1,
                             color: self.background
-                        });
+                        }, callback);
 
                         // Clear following rows
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: 0,
                             y: self.y,
                             w: self.w,
                             h: self.h - self.y,
                             color: self.background
-                        });
+                        }, callback);
                     },
                     1 => {
                         // Clear previous rows
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: 0,
                             y: 0,
                             w: self.w,
                             h: self.y,
                             color: self.background
-                        });
+                        }, callback);
 
                         // Clear current row to cursor
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: 0,
                             y: self.y,
                             w: self.x,
                             h: 1,
                             color: self.background
-                        });
+                        }, callback);
                     },
                     2 => {
                         // Erase all
@@ -337,13 +1099,17 @@ impl State {
                         self.y = 0;
 
                         // Clear all rows
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: 0,
                             y: 0,
                             w: self.w,
                             h: self.h,
                             color: self.background
-                        });
+                        }, callback);
+                    },
+                    3 => {
+                        // Erase saved lines
+                        self.scrollback.clear();
                     },
                     _ => {
                         debug!("Unknown CSI {:?} param {:?}", c, param);
@@ -351,64 +1117,117 @@ impl State {
                 }
             },
             'K' => { // EL (Erase in Line)
+                self.pending_wrap = false;
                 self.fix_cursor(callback);
 
                 let param = params.first().copied().unwrap_or(0);
                 match param {
                     0 => {
                         // Clear current row from cursor
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: self.x,
                             y: self.y,
                             w: self.w - self.x,
                             h: 1,
                             color: self.background
-                        });
+                        }, callback);
                     },
                     1 => {
                         // Clear current row to cursor
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: 0,
                             y: self.y,
                             w: self.x,
                             h: 1,
                             color: self.background
-                        });
+                        }, callback);
                     },
                     2 => {
                         // Erase row
-                        callback(Event::Rect {
+                        self.emit(Event::Rect {
                             x: 0,
                             y: self.y,
                             w: self.w,
                             h: 1,
                             color: self.background
-                        });
+                        }, callback);
                     },
                     _ => {
                         debug!("Unknown CSI {:?} param {:?}", c, param);
                     }
                 }
             },
+            'L' => { // IL (Insert Line)
+                self.pending_wrap = false;
+                // Lines outside of the scrolling region are not affected
+                if self.y >= self.top_margin && self.y <= self.bottom_margin && self.in_left_right_margins() {
+                    let param = params.first().copied().unwrap_or(1);
+                    let rows = cmp::min(self.bottom_margin + 1 - self.y, cmp::max(1, param) as usize);
+                    self.emit(Event::Move {
+                        from_x: self.left_margin,
+                        from_y: self.y,
+                        to_x: self.left_margin,
+                        to_y: self.y + rows,
+                        w: (self.right_margin + 1) - self.left_margin,
+                        h: (self.bottom_margin + 1) - (self.y + rows),
+                    }, callback);
+                    self.emit(Event::Rect {
+                        x: self.left_margin,
+                        y: self.y,
+                        w: (self.right_margin + 1) - self.left_margin,
+                        h: rows,
+                        color: self.background,
+                    }, callback);
+                    self.x = self.left_margin;
+                }
+            },
+            'M' => { // DL (Delete Line)
+                self.pending_wrap = false;
+                // Lines outside of the scrolling region are not affected
+                if self.y >= self.top_margin && self.y <= self.bottom_margin && self.in_left_right_margins() {
+                    let param = params.first().copied().unwrap_or(1);
+                    let rows = cmp::min(self.bottom_margin + 1 - self.y, cmp::max(1, param) as usize);
+                    self.emit(Event::Move {
+                        from_x: self.left_margin,
+                        from_y: self.y + rows,
+                        to_x: self.left_margin,
+                        to_y: self.y,
+                        w: (self.right_margin + 1) - self.left_margin,
+                        h: (self.bottom_margin + 1) - (self.y + rows),
+                    }, callback);
+                    self.emit(Event::Rect {
+                        x: self.left_margin,
+                        y: (self.bottom_margin + 1) - rows,
+                        w: (self.right_margin + 1) - self.left_margin,
+                        h: rows,
+                        color: self.background,
+                    }, callback);
+                    self.x = self.left_margin;
+                }
+            },
             'P' => { // DCH (Delete Character)
-                let param = params.first().copied().unwrap_or(1);
-                let cols = cmp::max(0, cmp::min(self.w as i64 - self.x as i64 - 1, param)) as usize;
-                //TODO: Use min and max to ensure correct behavior
-                callback(Event::Move {
-                    from_x: self.x + cols,
-                    from_y: self.y,
-                    to_x: self.x,
-                    to_y: self.y,
-                    w: self.w - (self.x + cols),
-                    h: 1,
-                });
-                callback(Event::Rect {
-                    x: self.w - cols,
-                    y: self.y,
-                    w: cols,
-                    h: 1,
-                    color: self.background,
-                });
+                self.pending_wrap = false;
+                // Characters outside of the left and right margins are not affected
+                if self.in_left_right_margins() {
+                    let param = params.first().copied().unwrap_or(1);
+                    let right = self.right_margin + 1;
+                    let cols = cmp::min(right - self.x, cmp::max(1, param) as usize);
+                    self.emit(Event::Move {
+                        from_x: self.x + cols,
+                        from_y: self.y,
+                        to_x: self.x,
+                        to_y: self.y,
+                        w: right - (self.x + cols),
+                        h: 1,
+                    }, callback);
+                    self.emit(Event::Rect {
+                        x: right - cols,
+                        y: self.y,
+                        w: cols,
+                        h: 1,
+                        color: self.background,
+                    }, callback);
+                }
             },
             'S' => { // SU (Scroll Up)
                 let param = params.first().copied().unwrap_or(1);
@@ -418,245 +1237,294 @@ impl State {
                 let param = params.first().copied().unwrap_or(1);
                 self.reverse_scroll(cmp::max(0, param) as usize, callback);
             },
+            'X' => { // ECH (Erase Character)
+                self.pending_wrap = false;
+                let param = params.first().copied().unwrap_or(1);
+                let cols = cmp::min(self.w.saturating_sub(self.x), cmp::max(1, param) as usize);
+                self.emit(Event::Rect {
+                    x: self.x,
+                    y: self.y,
+                    w: cols,
+                    h: 1,
+                    color: self.background,
+                }, callback);
+            },
+            'W' if intermediates == b"?" => { // DECST8C (Set Tab at Every 8 Columns)
+                let param = params.first().copied().unwrap_or(0);
+                match param {
+                    5 => self.reset_tabs(),
+                    _ => {
+                        debug!("Unknown CSI {:?} param {:?}", c, param);
+                    }
+                }
+            },
+            'Z' => { // CBT (Cursor Backward Tabulation)
+                self.pending_wrap = false;
+                let param = params.first().copied().unwrap_or(1);
+                for _ in 0..cmp::max(1, param) {
+                    self.x = self.previous_tab(self.x);
+                }
+            },
+            'b' => { // REP (Repeat Preceding Graphic Character)
+                if let Some(last_char) = self.last_char {
+                    let param = params.first().copied().unwrap_or(1);
+                    for _ in 0..cmp::max(1, param) {
+                        self.print_char(last_char, callback);
+                    }
+                }
+            },
             'c' => {
-                let report = "\x1B[?6c".to_string(); // VT102
-                callback(Event::Input {
+                let report = match intermediates.first() {
+                    None => self.identity.primary(), // DA1 (Primary Device Attributes)
+                    Some(b'>') => self.identity.secondary(), // DA2 (Secondary Device Attributes)
+                    Some(b'=') => self.identity.tertiary(), // DA3 (Tertiary Device Attributes)
+                    Some(inter) => {
+                        debug!("Unknown CSI {:?} intermediate {:?}", c, inter);
+                        return;
+                    }
+                };
+                self.emit(Event::Input {
                     data: &report.into_bytes()
-                });
+                }, callback);
             },
             'd' => { // VPA (Line Position Absolute)
+                self.pending_wrap = false;
                 let param = params.first().copied().unwrap_or(1);
                 self.y = cmp::max(0, cmp::min(self.h as i64 - 1, param - 1)) as usize;
             },
-            'h' => { // DECSET (DEC Private Mode Set)
-                //TODO: Check intermediate
+            'g' => { // TBC (Tab Clear)
                 let param = params.first().copied().unwrap_or(0);
                 match param {
-                    3 => { // DECCOLM (132 Column Mode) VT100
-                        self.x = 0;
-                        self.y = 0;
-                        self.top_margin = 0;
-                        self.bottom_margin = cmp::max(0, self.h as isize - 1) as usize;
-
-                        self.w = 132;
-                        //Resize screen
-                        callback(Event::Resize {
-                            w: self.w,
-                            h: self.h
-                        });
-
-                        // Clear screen
-                        callback(Event::Rect {
-                            x: 0,
-                            y: 0,
-                            w: self.w,
-                            h: self.h,
-                            color: self.background
-                        });
-                    },
-                    6 => { // DECOM (Origin Mode) VT100
-                        self.origin = true;
-                        self.x = 0;
-                        self.y = self.top_margin;
+                    0 => if let Some(tab) = self.tabs.get_mut(self.x) {
+                        *tab = false;
                     },
-                    7 => self.autowrap = true, // DECAWM (Auto-Wrap Mode) VT100
-                    25 => self.cursor = true, // DECTCEM (Show Cursor) VT220
-                    47 => callback(Event::ScreenBuffer {
-                        alternate: true,
-                        clear: false,
-                    }),
-                    1000 => self.mouse_vt200 = true,
-                    1002 => self.mouse_btn = true,
-                    1006 => self.mouse_sgr = true,
-                    1015 => self.mouse_rxvt = true,
-                    1047 => callback(Event::ScreenBuffer {
-                        alternate: true,
-                        clear: false,
-                    }),
-                    1048 => {
-                        self.save_x = self.x;
-                        self.save_y = self.y;
+                    3 => for tab in self.tabs.iter_mut() {
+                        *tab = false;
                     },
-                    1049 => {
-                        self.save_x = self.x;
-                        self.save_y = self.y;
-
-                        callback(Event::ScreenBuffer {
-                            alternate: true,
-                            clear: true,
-                        });
-                    },
-                    unknown => {
-                        debug!("Unknown CSI {:?} param {:?}", c, unknown);
+                    _ => {
+                        debug!("Unknown CSI {:?} param {:?}", c, param);
                     }
                 }
             },
-            'l' => { // DECRST (DEC Private Reset Mode)
-                //TODO: Check intermediate
-                let param = params.first().copied().unwrap_or(0);
-                match param {
-                    3 => { // DECCOLM (80 Column Mode) VT100
-                        self.x = 0;
-                        self.y = 0;
-                        self.top_margin = 0;
-                        self.bottom_margin = cmp::max(0, self.h as isize - 1) as usize;
-
-                        self.w = 80;
-                        //Resize screen
-                        callback(Event::Resize {
-                            w: self.w,
-                            h: self.h
-                        });
-
-                        // Clear screen
-                        callback(Event::Rect {
-                            x: 0,
-                            y: 0,
-                            w: self.w,
-                            h: self.h,
-                            color: self.background
-                        });
-                    },
-                    6 => { // DECOM (Normal Cursor Mode) VT100
-                        self.origin = false;
-                        self.x = 0;
-                        self.y = 0;
-                    },
-                    7 => self.autowrap = false, // DECAWM (No Auto-Wrap Mode) VT100
-                    25 => self.cursor = false, // DECTCEM (Hide Cursor) VT220
-                    47 => callback(Event::ScreenBuffer {
-                        alternate: false,
-                        clear: false,
-                    }),
-                    1000 => self.mouse_vt200 = false,
-                    1002 => self.mouse_btn = false,
-                    1006 => self.mouse_sgr = false,
-                    1015 => self.mouse_rxvt = false,
-                    1047 => callback(Event::ScreenBuffer {
-                        alternate: false,
-                        clear: true
-                    }),
-                    1048 => {
-                        self.x = self.save_x;
-                        self.y = self.save_y;
-                    },
-                    1049 => {
-                        self.x = self.save_x;
-                        self.y = self.save_y;
-
-                        callback(Event::ScreenBuffer {
-                            alternate: false,
-                            clear: false,
-                        });
-                    }
-                    unknown => {
-                        debug!("Unknown CSI {:?} param {:?}", c, unknown);
+            'h' | 'l' if intermediates.is_empty() || intermediates == b"?" => {
+                // SM (Set Mode), RM (Reset Mode), or with '?', DECSET (DEC Private Mode Set), DECRST (DEC Private Mode Reset)
+                let dec = ! intermediates.is_empty();
+                for &param in params.iter() {
+                    let mode = if dec {
+                        Mode::dec(param as u16)
+                    } else {
+                        Mode::ansi(param as u16)
+                    };
+                    match mode {
+                        Some(mode) => self.set_mode(mode, c == 'h', callback),
+                        None => {
+                            debug!("Unknown CSI {:?} param {:?}", c, param);
+                        }
                     }
                 }
             },
-            'm' => { // SGR (Select Graphic Rendition)
+            'm' if intermediates.is_empty() => { // SGR (Select Graphic Rendition)
                 // Display attributes
-                let mut value_iter = if params.is_empty() {
-                    [0].iter()
-                } else {
-                    params.iter()
-                };
-                while let Some(value) = value_iter.next() {
-                    match *value {
+                let mut param_iter = subparams.iter().copied();
+                while let Some(param) = param_iter.next() {
+                    let value = param.first().copied().unwrap_or(0);
+                    match value {
                         0 => { // default
-                            self.foreground = self.foreground_default;
-                            self.background = self.background_default;
-                            self.bold = false;
-                            self.underlined = false;
-                            self.inverted = false;
+                            self.reset_attributes();
                         },
                         1 => {
                             self.bold = true;
                         },
+                        2 => {
+                            self.dim = true;
+                        },
                         3 => {
                             self.italic = true;
                         },
-                        4 => {
-                            self.underlined = true;
+                        4 => match param.get(1).copied().unwrap_or(1) {
+                            0 => self.underline = Underline::None,
+                            1 => self.underline = Underline::Single,
+                            2 => self.underline = Underline::Double,
+                            3 => self.underline = Underline::Curly,
+                            4 => self.underline = Underline::Dotted,
+                            5 => self.underline = Underline::Dashed,
+                            style => {
+                                debug!("Unknown CSI {:?} underline style {:?}", c, style);
+                            }
+                        },
+                        5 => {
+                            self.blink = true;
+                        },
+                        6 => {
+                            self.rapid_blink = true;
                         },
                         7 => {
                             self.inverted = true;
                         },
+                        8 => {
+                            self.hidden = true;
+                        },
                         9 => {
                             self.strikethrough = true;
                         },
                         21 => {
+                            self.underline = Underline::Double;
+                        },
+                        22 => {
                             self.bold = false;
+                            self.dim = false;
                         },
                         23 => {
                             self.italic = false;
                         },
                         24 => {
-                            self.underlined = false;
+                            self.underline = Underline::None;
+                        },
+                        25 => {
+                            self.blink = false;
+                            self.rapid_blink = false;
                         },
                         27 => {
                             self.inverted = false;
                         },
+                        28 => {
+                            self.hidden = false;
+                        },
                         29 => {
                             self.strikethrough = false;
                         },
-                        30 ..= 37 => self.foreground = Color::Ansi(*value as u8 - 30),
-                        38 => match value_iter.next().copied().unwrap_or(0) {
-                            2 => {
-                                //True color
-                                let r = value_iter.next().copied().unwrap_or(0);
-                                let g = value_iter.next().copied().unwrap_or(0);
-                                let b = value_iter.next().copied().unwrap_or(0);
-                                self.foreground = Color::TrueColor(r as u8, g as u8, b as u8);
-                            },
-                            5 => {
-                                //256 color
-                                let color_value = value_iter.next().copied().unwrap_or(0);
-                                self.foreground = Color::Ansi(color_value as u8);
-                            },
-                            _ => {}
+                        30 ..= 37 => self.foreground = Color::Ansi(value as u8 - 30),
+                        38 => if let Some(color) = sgr_color(param, &mut param_iter) {
+                            self.foreground = color;
                         },
                         39 => {
                             self.foreground = self.foreground_default;
                         },
-                        40 ..= 47 => self.background = Color::Ansi(*value as u8 - 40),
-                        48 => match value_iter.next().copied().unwrap_or(0) {
-                            2 => {
-                                //True color
-                                let r = value_iter.next().copied().unwrap_or(0);
-                                let g = value_iter.next().copied().unwrap_or(0);
-                                let b = value_iter.next().copied().unwrap_or(0);
-                                self.background = Color::TrueColor(r as u8, g as u8, b as u8);
-                            },
-                            5 => {
-                                //256 color
-                                let color_value = value_iter.next().copied().unwrap_or(0);
-                                self.background = Color::Ansi(color_value as u8);
-                            },
-                            _ => {}
+                        40 ..= 47 => self.background = Color::Ansi(value as u8 - 40),
+                        48 => if let Some(color) = sgr_color(param, &mut param_iter) {
+                            self.background = color;
                         },
                         49 => {
                             self.background = self.background_default;
                         },
+                        53 => {
+                            self.overline = true;
+                        },
+                        55 => {
+                            self.overline = false;
+                        },
+                        58 => if let Some(color) = sgr_color(param, &mut param_iter) {
+                            self.underline_color = Some(color);
+                        },
+                        59 => {
+                            self.underline_color = None;
+                        },
+                        90 ..= 97 => self.foreground = Color::Ansi(value as u8 - 90 + 8),
+                        100 ..= 107 => self.background = Color::Ansi(value as u8 - 100 + 8),
                         _ => {
-                            debug!("Unknown CSI {:?} param {:?}", c, value);
+                            debug!("Unknown CSI {:?} param {:?}", c, param);
                         },
                     }
                 }
             },
-            'n' => {
+            'm' => match intermediates.first() {
+                Some(b'>') => { // XTMODKEYS (Set Key Modifier Options)
+                    let resource = params.first().copied().unwrap_or(0);
+                    match resource {
+                        4 => self.modify_other_keys = params.get(1).copied().unwrap_or(0).clamp(0, 2) as u8,
+                        _ => {
+                            debug!("Unknown CSI {:?} resource {:?}", c, resource);
+                        }
+                    }
+                },
+                Some(b'?') => { // XTQMODKEYS (Query Key Modifier Options)
+                    let resource = params.first().copied().unwrap_or(0);
+                    match resource {
+                        4 => {
+                            let report = format!("\x1B[>4;{}m", self.modify_other_keys);
+                            self.emit(Event::Input {
+                                data: &report.into_bytes()
+                            }, callback);
+                        },
+                        _ => {
+                            debug!("Unknown CSI {:?} resource {:?}", c, resource);
+                        }
+                    }
+                },
+                inter => {
+                    debug!("Unknown CSI {:?} intermediate {:?}", c, inter);
+                }
+            },
+            'n' if intermediates.first() == Some(&b'>') => { // XTMODKEYS (Disable Key Modifier Options)
+                let resource = params.first().copied().unwrap_or(0);
+                match resource {
+                    4 => self.modify_other_keys = 0,
+                    _ => {
+                        debug!("Unknown CSI {:?} resource {:?}", c, resource);
+                    }
+                }
+            },
+            'n' if intermediates.is_empty() => { // DSR (Device Status Report)
                 let param = params.first().copied().unwrap_or(0);
-                match param {
-                    6 => {
-                        let report = format!("\x1B[{};{}R", self.y + 1, self.x + 1);
-                        callback(Event::Input {
-                            data: &report.into_bytes()
-                        });
+                let report = match param {
+                    5 => "\x1B[0n".to_string(), // Operating status, no malfunction
+                    6 => { // CPR (Cursor Position Report)
+                        let (x, y) = self.report_position();
+                        format!("\x1B[{};{}R", y, x)
                     },
                     _ => {
                         debug!("Unknown CSI {:?} param {:?}", c, param);
+                        return;
                     }
-                }
+                };
+                self.emit(Event::Input {
+                    data: &report.into_bytes()
+                }, callback);
+            },
+            'n' if intermediates == b"?" => { // DSR (DEC Private Device Status Report)
+                let param = params.first().copied().unwrap_or(0);
+                let report = match param {
+                    6 => { // DECXCPR (Extended Cursor Position Report), on page 1
+                        let (x, y) = self.report_position();
+                        format!("\x1B[?{};{};1R", y, x)
+                    },
+                    15 => "\x1B[?13n".to_string(), // Printer status, no printer
+                    25 => "\x1B[?20n".to_string(), // UDK status, unlocked
+                    26 => "\x1B[?27;1;0;0n".to_string(), // Keyboard status, North American, ready, LK201
+                    53 | 55 => "\x1B[?50n".to_string(), // Locator status, no locator
+                    996 => { // Color scheme, dark or light
+                        let scheme = if self.background_default.is_dark() { 1 } else { 2 };
+                        format!("\x1B[?997;{}n", scheme)
+                    },
+                    _ => {
+                        debug!("Unknown CSI {:?} param {:?}", c, param);
+                        return;
+                    }
+                };
+                self.emit(Event::Input {
+                    data: &report.into_bytes()
+                }, callback);
+            },
+            'p' if intermediates == b"$" || intermediates == b"?$" => { // DECRQM (Request Mode)
+                let dec = intermediates[0] == b'?';
+                let param = params.first().copied().unwrap_or(0);
+                let mode = if dec {
+                    Mode::dec(param as u16)
+                } else {
+                    Mode::ansi(param as u16)
+                };
+                // Unrecognized modes are reported as 0
+                let value = mode.map_or(0, |mode| self.mode(mode) as u8);
+                let report = format!("\x1B[{}{};{}$y", if dec { "?" } else { "" }, param, value);
+                self.emit(Event::Input {
+                    data: &report.into_bytes()
+                }, callback);
+            },
+            'q' if intermediates == b">" => { // XTVERSION (Report Terminal Name and Version)
+                let report = self.identity.xtversion();
+                self.emit(Event::Input {
+                    data: &report.into_bytes()
+                }, callback);
             },
             'r' => {
                 let top = params.first().copied().unwrap_or(1);
@@ -664,33 +1532,160 @@ impl State {
                 self.top_margin = cmp::max(0, cmp::min(self.h as isize - 1, top as isize - 1)) as usize;
                 self.bottom_margin = cmp::max(self.top_margin as isize, cmp::min(self.h as isize - 1, bottom as isize - 1)) as usize;
             },
+            's' if self.left_right_margins => { // DECSLRM (Set Left and Right Margins)
+                let left = cmp::max(1, params.first().copied().unwrap_or(1));
+                let right = match params.get(1).copied() {
+                    Some(right) if right > 0 => right,
+                    _ => self.w as i64,
+                };
+                let left = (cmp::min(self.w as i64, left) as usize).saturating_sub(1);
+                let right = (cmp::min(self.w as i64, right) as usize).saturating_sub(1);
+                // The margins must be at least two columns apart
+                if left < right {
+                    self.left_margin = left;
+                    self.right_margin = right;
+                    self.pending_wrap = false;
+                    self.x = if self.origin { self.left_margin } else { 0 };
+                    self.y = if self.origin { self.top_margin } else { 0 };
+                }
+            },
             's' => { // SCP,SCOSC (Save Current Cursor Position)
-                self.save_x = self.x;
-                self.save_y = self.y;
+                self.save_cursor();
             },
-            'u' => { // RCP,SCORC (Restore Saved Cursor Position)
-                self.x = self.save_x;
-                self.y = self.save_y;
+            'u' => match intermediates.first() {
+                None => { // RCP,SCORC (Restore Saved Cursor Position)
+                    self.restore_cursor();
+                },
+                Some(b'?') => { // Query kitty keyboard flags
+                    let report = format!("\x1B[?{}u", self.kitty_keyboard.last().copied().unwrap_or(0));
+                    self.emit(Event::Input {
+                        data: &report.into_bytes()
+                    }, callback);
+                },
+                Some(b'>') => { // Push kitty keyboard flags
+                    let flags = params.first().copied().unwrap_or(0);
+                    // Limit the size of the stack by removing the oldest entries
+                    if self.kitty_keyboard.len() >= 16 {
+                        self.kitty_keyboard.remove(0);
+                    }
+                    self.kitty_keyboard.push(flags as u8 & 0x1F);
+                },
+                Some(b'<') => { // Pop kitty keyboard flags
+                    let count = cmp::max(1, params.first().copied().unwrap_or(1)) as usize;
+                    let len = self.kitty_keyboard.len().saturating_sub(count);
+                    self.kitty_keyboard.truncate(len);
+                },
+                Some(b'=') => { // Set kitty keyboard flags
+                    let flags = params.first().copied().unwrap_or(0) as u8 & 0x1F;
+                    let current = self.kitty_keyboard.last().copied().unwrap_or(0);
+                    let flags = match params.get(1).copied().unwrap_or(1) {
+                        1 => flags,
+                        2 => current | flags,
+                        3 => current & ! flags,
+                        mode => {
+                            debug!("Unknown CSI {:?} mode {:?}", c, mode);
+                            current
+                        }
+                    };
+                    match self.kitty_keyboard.last_mut() {
+                        Some(last) => *last = flags,
+                        None => self.kitty_keyboard.push(flags),
+                    }
+                },
+                Some(inter) => {
+                    debug!("Unknown CSI {:?} intermediate {:?}", c, inter);
+                }
             },
-            '@' => {
-                let param = params.first().copied().unwrap_or(1);
-                let cols = cmp::max(0, cmp::min(self.w as i64 - self.x as i64 - 1, param)) as usize;
-                //TODO: Use min and max to ensure correct behavior
-                callback(Event::Move {
-                    from_x: self.x,
-                    from_y: self.y,
-                    to_x: self.x + cols,
-                    to_y: self.y,
-                    w: self.w - (self.x + cols),
-                    h: 1,
-                });
-                callback(Event::Rect {
-                    x: self.x,
-                    y: self.y,
-                    w: cols,
-                    h: 1,
-                    color: self.background,
-                });
+            'w' if intermediates == b"$" => { // DECRQPSR (Request Presentation State Report)
+                let param = params.first().copied().unwrap_or(0);
+                match param {
+                    2 => { // DECTABSR (Tab Stop Report)
+                        let stops: Vec<String> = self.tabs.iter()
+                            .enumerate()
+                            .filter(|&(_, &tab)| tab)
+                            .map(|(x, _)| (x + 1).to_string())
+                            .collect();
+                        let report = format!("\x1BP2$u{}\x1B\\", stops.join("/"));
+                        self.emit(Event::Input {
+                            data: &report.into_bytes()
+                        }, callback);
+                    },
+                    _ => {
+                        debug!("Unknown CSI {:?} param {:?}", c, param);
+                    }
+                }
+            },
+            '@' => { // ICH (Insert Character)
+                self.pending_wrap = false;
+                // Characters outside of the left and right margins are not affected
+                if self.in_left_right_margins() {
+                    let param = params.first().copied().unwrap_or(1);
+                    let right = self.right_margin + 1;
+                    let cols = cmp::min(right - self.x, cmp::max(1, param) as usize);
+                    self.emit(Event::Move {
+                        from_x: self.x,
+                        from_y: self.y,
+                        to_x: self.x + cols,
+                        to_y: self.y,
+                        w: right - (self.x + cols),
+                        h: 1,
+                    }, callback);
+                    self.emit(Event::Rect {
+                        x: self.x,
+                        y: self.y,
+                        w: cols,
+                        h: 1,
+                        color: self.background,
+                    }, callback);
+                }
+            },
+            '}' if intermediates == b"'" => { // DECIC (Insert Column)
+                // Columns are only inserted within the scrolling region
+                if self.y >= self.top_margin && self.y <= self.bottom_margin && self.in_left_right_margins() {
+                    let param = params.first().copied().unwrap_or(1);
+                    let right = self.right_margin + 1;
+                    let cols = cmp::min(right - self.x, cmp::max(1, param) as usize);
+                    let rows = (self.bottom_margin + 1) - self.top_margin;
+                    self.emit(Event::Move {
+                        from_x: self.x,
+                        from_y: self.top_margin,
+                        to_x: self.x + cols,
+                        to_y: self.top_margin,
+                        w: right - (self.x + cols),
+                        h: rows,
+                    }, callback);
+                    self.emit(Event::Rect {
+                        x: self.x,
+                        y: self.top_margin,
+                        w: cols,
+                        h: rows,
+                        color: self.background,
+                    }, callback);
+                }
+            },
+            '~' if intermediates == b"'" => { // DECDC (Delete Column)
+                // Columns are only deleted within the scrolling region
+                if self.y >= self.top_margin && self.y <= self.bottom_margin && self.in_left_right_margins() {
+                    let param = params.first().copied().unwrap_or(1);
+                    let right = self.right_margin + 1;
+                    let cols = cmp::min(right - self.x, cmp::max(1, param) as usize);
+                    let rows = (self.bottom_margin + 1) - self.top_margin;
+                    self.emit(Event::Move {
+                        from_x: self.x + cols,
+                        from_y: self.top_margin,
+                        to_x: self.x,
+                        to_y: self.top_margin,
+                        w: right - (self.x + cols),
+                        h: rows,
+                    }, callback);
+                    self.emit(Event::Rect {
+                        x: right - cols,
+                        y: self.top_margin,
+                        w: cols,
+                        h: rows,
+                        color: self.background,
+                    }, callback);
+                }
             },
             _ => {
                 debug!("Unknown CSI {:?} params {:?}", c, params);
@@ -699,15 +1694,60 @@ impl State {
     }
 
     pub fn esc<F: FnMut(Event)>(&mut self, c: char, intermediates: &[u8], callback: &mut F) {
+        self.last_print = None;
+
+        // Designate a character set to G0 through G3
+        let designate = match intermediates.first() {
+            Some(b'(') => Some(0),
+            Some(b')') | Some(b'-') => Some(1),
+            Some(b'*') | Some(b'.') => Some(2),
+            Some(b'+') | Some(b'/') => Some(3),
+            _ => None,
+        };
+        if let Some(g) = designate {
+            match Charset::from_designation(intermediates, c) {
+                Some(charset) => match g {
+                    0 => self.g0 = charset,
+                    1 => self.g1 = charset,
+                    2 => self.g2 = charset,
+                    _ => self.g3 = charset,
+                },
+                None => {
+                    debug!("Unknown charset {:?} {:?} for G{}", intermediates, c, g);
+                }
+            }
+            return;
+        }
+
         match c {
             'D' => { // IND (Index) [ECMA-48 - depreciated in 4th edition, removed in 5th edition]
+                self.pending_wrap = false;
                 self.y += 1;
             },
             'E' => { // NEL (Next Line)
-                self.x = 0;
+                self.pending_wrap = false;
+                self.x = if self.x >= self.left_margin { self.left_margin } else { 0 };
                 self.y += 1;
             },
+            'H' => { // HTS (Horizontal Tab Set)
+                if let Some(tab) = self.tabs.get_mut(self.x) {
+                    *tab = true;
+                }
+            },
+            'N' => { // SS2 (Single Shift 2)
+                self.single_shift = Some(2);
+            },
+            'O' => { // SS3 (Single Shift 3)
+                self.single_shift = Some(3);
+            },
+            'n' => { // LS2 (Locking Shift 2)
+                self.gl = 2;
+            },
+            'o' => { // LS3 (Locking Shift 3)
+                self.gl = 3;
+            },
             'M' => { // RI (Reverse Index/Line Feed)
+                self.pending_wrap = false;
                 while self.y <= self.top_margin {
                     self.reverse_scroll(1, callback);
                     self.y += 1;
@@ -716,8 +1756,7 @@ impl State {
             },
             '7' => { // DECSC (DEC Save Cursor)
                 // Save
-                self.save_x = self.x;
-                self.save_y = self.y;
+                self.save_cursor();
             },
             '8' => { // DECRC (DEC Restore Cursor)
                 match intermediates.first().map(|v| *v as char) {
@@ -727,60 +1766,78 @@ impl State {
                             self.x = x;
 
                             self.y = 8;
-                            self.block('E', callback);
+                            self.block('E', false, callback);
 
                             self.y = 15;
-                            self.block('E', callback);
+                            self.block('E', false, callback);
                         }
 
                         for y in 9..15 {
                             self.y = y;
 
                             self.x = (self.w/2).checked_sub(30).unwrap_or(10);
-                            self.block('E', callback);
+                            self.block('E', false, callback);
 
                             self.x = (self.w/2).checked_add(29).unwrap_or(69);
-                            self.block('E', callback);
+                            self.block('E', false, callback);
                         }
 
                         self.x = 0;
                         self.y = 0;
+                        self.pending_wrap = false;
                     },
                     Some(inter) => {
                         debug!("Unknown ESC {:?} intermediate {:?}", c, inter);
                     },
                     None => {
                         // Restore
-                        self.x = self.save_x;
-                        self.y = self.save_y;
+                        self.restore_cursor();
                     }
                 }
             },
+            '=' => { // DECKPAM (Application Keypad)
+                self.keypad = true;
+            },
+            '>' => { // DECKPNM (Normal Keypad)
+                self.keypad = false;
+            },
             'c' => { // RIS (Reset to Initial State)
                 // Reset
+                self.switch_screen(false, callback);
                 self.x = 0;
                 self.y = 0;
-                self.save_x = 0;
-                self.save_y = 0;
+                self.pending_wrap = false;
                 self.top_margin = 0;
                 self.bottom_margin = cmp::max(0, self.h as isize - 1) as usize;
+                self.left_margin = 0;
+                self.right_margin = cmp::max(0, self.w as isize - 1) as usize;
+                self.left_right_margins = false;
+                self.reset_tabs();
+                self.last_char = None;
                 self.cursor = true;
-                self.g0 = 'B';
-                self.g1 = '0';
-                self.foreground = self.foreground_default;
-                self.background = self.background_default;
-                self.bold = false;
-                self.inverted = false;
-                self.underlined = false;
+                self.cursor_keys = false;
+                self.keypad = false;
+                self.kitty_keyboard.clear();
+                self.modify_other_keys = 0;
+                self.bracketed_paste = false;
+                self.set_focus_reporting(false, callback);
+                self.insert = false;
+                self.newline = false;
+                self.inactive_kitty_keyboard.clear();
+                self.g0 = Charset::Ascii;
+                self.g1 = Charset::DecSpecialGraphics;
+                self.g2 = Charset::Ascii;
+                self.g3 = Charset::Ascii;
+                self.gl = 0;
+                self.single_shift = None;
+                self.nrcs = false;
+                self.reset_attributes();
+                self.save = SavedCursor::new(self.foreground_default, self.background_default);
+                self.inactive_save = self.save;
+                self.inactive_screen = Screen::new(self.w, self.h);
 
                 // Clear screen
-                callback(Event::Rect {
-                    x: 0,
-                    y: 0,
-                    w: self.w,
-                    h: self.h,
-                    color: self.background
-                });
+                self.clear_screen(callback);
 
                 self.redraw = true;
             },
@@ -794,9 +1851,9 @@ impl State {
         match params.first().map(|s| s.first().copied().unwrap_or(0)).unwrap_or(0) as char {
             '0' | '1' | '2' => if let Some(bytes) = params.get(1) {
                 if let Ok(string) = str::from_utf8(bytes) {
-                    callback(Event::Title {
+                    self.emit(Event::Title {
                         title: string.to_string()
-                    });
+                    }, callback);
                 } else {
                     debug!("Invalid UTF-8 {:?}", bytes);
                 }
@@ -826,7 +1883,7 @@ impl<'a, F: FnMut(Event)> vte::Perform for Performer<'a, F> {
         self.state.execute(byte as char, self.callback);
     }
 
-    fn hook(&mut self, _params: &[i64], _intermediates: &[u8], _ignore: bool, _action: char) {
+    fn hook(&mut self, _params: &vte::Params, _intermediates: &[u8], _ignore: bool, _action: char) {
         trace!("[hook] params={:?}, intermediates={:?}, ignore={:?}, action={:?}", _params, _intermediates, _ignore, _action);
     }
 
@@ -843,9 +1900,15 @@ impl<'a, F: FnMut(Event)> vte::Perform for Performer<'a, F> {
         self.state.osc(params, self.callback);
     }
 
-    fn csi_dispatch(&mut self, params: &[i64], intermediates: &[u8], _ignore: bool, c: char) {
+    fn csi_dispatch(&mut self, params: &vte::Params, intermediates: &[u8], _ignore: bool, c: char) {
         trace!("[csi] params={:?}, intermediates={:?}, ignore={:?}, char={:?} at {}, {}", params, intermediates, _ignore, c, self.state.x, self.state.y);
-        self.state.csi(c, params, intermediates, self.callback);
+        let mut subparams: [&[u16]; 32] = [&[]; 32];
+        let mut len = 0;
+        for (subparam, param) in subparams.iter_mut().zip(params.iter()) {
+            *subparam = param;
+            len += 1;
+        }
+        self.state.csi_subparams(c, &subparams[..len], intermediates, self.callback);
     }
 
     fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
@@ -873,8 +1936,86 @@ impl Console {
         state.top_margin = cmp::max(0, cmp::min(h as isize - 1, state.top_margin as isize)) as usize;
         state.bottom_margin = cmp::max(state.top_margin as isize, cmp::min(h as isize - 1, state.bottom_margin as isize + h as isize - state.h as isize)) as usize;
 
+        state.left_margin = cmp::max(0, cmp::min(w as isize - 1, state.left_margin as isize)) as usize;
+        state.right_margin = cmp::max(state.left_margin as isize, cmp::min(w as isize - 1, state.right_margin as isize + w as isize - state.w as isize)) as usize;
+
         state.w = w;
         state.h = h;
+        state.resize_tabs();
+        state.screen.resize(w, h);
+        state.inactive_screen.resize(w, h);
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.state.screen
+    }
+
+    pub fn scrollback(&self) -> &Scrollback {
+        &self.state.scrollback
+    }
+
+    pub fn scrollback_mut(&mut self) -> &mut Scrollback {
+        &mut self.state.scrollback
+    }
+
+    /// Get a row of the viewport, taking the scrollback offset into account
+    pub fn viewport_row(&self, y: usize) -> Option<&[Cell]> {
+        let scrollback = &self.state.scrollback;
+        let offset = scrollback.offset();
+        if y < offset {
+            scrollback.line(scrollback.len() - offset + y)
+        } else {
+            self.state.screen.row(y - offset)
+        }
+    }
+
+    /// Encode a mouse event for the active mouse modes, returning `None` if it should not be reported
+    pub fn encode_mouse(&self, event: &MouseEvent) -> Option<Vec<u8>> {
+        event.encode(&self.state)
+    }
+
+    /// Encode a key press for the active keyboard modes, returning `None` if it cannot be sent
+    pub fn encode_key(&self, key: Key, modifiers: Modifiers) -> Option<Vec<u8>> {
+        self.encode_key_event(&KeyEvent::new(key, modifiers))
+    }
+
+    /// Encode a key event, including releases and the details used by the kitty keyboard protocol
+    pub fn encode_key_event(&self, event: &KeyEvent) -> Option<Vec<u8>> {
+        event.encode(&self.state)
+    }
+
+    /// Encode pasted text, wrapping it in markers when bracketed paste is enabled
+    ///
+    /// Control characters and end markers are removed, so the text cannot end the paste early.
+    pub fn encode_paste(&self, text: &str) -> Vec<u8> {
+        let mut report = String::with_capacity(text.len() + 12);
+        if self.state.bracketed_paste {
+            report.push_str("\x1B[200~");
+        }
+
+        let text = text.replace("\x1B[201~", "").replace("\r\n", "\r");
+        for c in text.chars() {
+            match c {
+                '\n' => report.push('\r'),
+                '\t' | '\r' => report.push(c),
+                _ if c.is_control() => (),
+                _ => report.push(c),
+            }
+        }
+
+        if self.state.bracketed_paste {
+            report.push_str("\x1B[201~");
+        }
+        report.into_bytes()
+    }
+
+    /// Encode a change of focus, returning `None` if focus reporting is disabled
+    pub fn focus_changed(&self, focused: bool) -> Option<Vec<u8>> {
+        if self.state.focus_reporting {
+            Some(if focused { b"\x1B[I".to_vec() } else { b"\x1B[O".to_vec() })
+        } else {
+            None
+        }
     }
 
     pub fn write<F: FnMut(Event)>(&mut self, bytes: &[u8], mut callback: F) {
@@ -0,0 +1,140 @@
+/// A mode changed by SM/RM (ANSI modes) or DECSET/DECRST (DEC private modes)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// KAM (Keyboard Action Mode)
+    KeyboardAction,
+    /// IRM (Insert Mode)
+    Insert,
+    /// SRM (Send/Receive Mode)
+    SendReceive,
+    /// LNM (Automatic Newline)
+    Newline,
+    /// DECCKM (Application Cursor Keys)
+    CursorKeys,
+    /// DECANM (ANSI Mode), which switches to VT52 mode when reset
+    Ansi,
+    /// DECCOLM (132 Column Mode)
+    Column,
+    /// DECSCLM (Smooth Scroll)
+    SmoothScroll,
+    /// DECSCNM (Reverse Video)
+    ReverseVideo,
+    /// DECOM (Origin Mode)
+    Origin,
+    /// DECAWM (Auto-Wrap Mode)
+    Autowrap,
+    /// X10 mouse reporting
+    MouseX10,
+    /// DECTCEM (Show Cursor)
+    ShowCursor,
+    /// DECNRCM (National Replacement Character Set Mode)
+    Nrcs,
+    /// DECLRMM (Left Right Margin Mode)
+    LeftRightMargins,
+    /// Alternate screen, without clearing or saving the cursor
+    AlternateScreenLegacy,
+    /// VT200 mouse reporting
+    MouseVt200,
+    /// Button event mouse reporting
+    MouseBtn,
+    /// Any event mouse reporting
+    MouseAny,
+    /// Focus in and out reporting
+    FocusReporting,
+    /// UTF-8 mouse encoding
+    MouseUtf8,
+    /// SGR mouse encoding
+    MouseSgr,
+    /// urxvt mouse encoding
+    MouseRxvt,
+    /// SGR mouse encoding in pixels
+    MouseSgrPixels,
+    /// Alternate screen, cleared when leaving it
+    AlternateScreen,
+    /// Save the cursor when set, restore it when reset
+    SaveCursor,
+    /// Alternate screen, saving the cursor and clearing when entering it
+    AlternateScreenSaveCursor,
+    /// Bracketed paste
+    BracketedPaste,
+}
+
+/// The value of a mode, as reported by DECRPM (Report Mode)
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ModeValue {
+    Set = 1,
+    Reset = 2,
+    PermanentlySet = 3,
+    PermanentlyReset = 4,
+}
+
+impl From<bool> for ModeValue {
+    fn from(value: bool) -> ModeValue {
+        if value {
+            ModeValue::Set
+        } else {
+            ModeValue::Reset
+        }
+    }
+}
+
+// Modes used by SM and RM
+const ANSI_MODES: &[(u16, Mode)] = &[
+    (2, Mode::KeyboardAction),
+    (4, Mode::Insert),
+    (12, Mode::SendReceive),
+    (20, Mode::Newline),
+];
+
+// Modes used by DECSET and DECRST
+const DEC_MODES: &[(u16, Mode)] = &[
+    (1, Mode::CursorKeys),
+    (2, Mode::Ansi),
+    (3, Mode::Column),
+    (4, Mode::SmoothScroll),
+    (5, Mode::ReverseVideo),
+    (6, Mode::Origin),
+    (7, Mode::Autowrap),
+    (9, Mode::MouseX10),
+    (25, Mode::ShowCursor),
+    (42, Mode::Nrcs),
+    (47, Mode::AlternateScreenLegacy),
+    (69, Mode::LeftRightMargins),
+    (1000, Mode::MouseVt200),
+    (1002, Mode::MouseBtn),
+    (1003, Mode::MouseAny),
+    (1004, Mode::FocusReporting),
+    (1005, Mode::MouseUtf8),
+    (1006, Mode::MouseSgr),
+    (1015, Mode::MouseRxvt),
+    (1016, Mode::MouseSgrPixels),
+    (1047, Mode::AlternateScreen),
+    (1048, Mode::SaveCursor),
+    (1049, Mode::AlternateScreenSaveCursor),
+    (2004, Mode::BracketedPaste),
+];
+
+impl Mode {
+    /// Find the ANSI mode with the given parameter
+    pub fn ansi(param: u16) -> Option<Mode> {
+        ANSI_MODES.iter().find(|&&(p, _)| p == param).map(|&(_, mode)| mode)
+    }
+
+    /// Find the DEC private mode with the given parameter
+    pub fn dec(param: u16) -> Option<Mode> {
+        DEC_MODES.iter().find(|&&(p, _)| p == param).map(|&(_, mode)| mode)
+    }
+
+    /// Whether this is a DEC private mode, which is prefixed by `?`
+    pub fn is_dec(self) -> bool {
+        DEC_MODES.iter().any(|&(_, mode)| mode == self)
+    }
+
+    /// The parameter used to select this mode
+    pub fn param(self) -> u16 {
+        ANSI_MODES.iter()
+            .chain(DEC_MODES.iter())
+            .find(|&&(_, mode)| mode == self)
+            .map_or(0, |&(param, _)| param)
+    }
+}
@@ -0,0 +1,35 @@
+/// Modifier keys held during a key press or mouse event
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub ctrl: bool,
+    pub super_key: bool,
+    pub hyper: bool,
+    pub meta: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+impl Modifiers {
+    pub fn is_empty(self) -> bool {
+        ! (self.shift || self.alt || self.ctrl || self.meta)
+    }
+
+    /// The xterm modifier parameter, where 1 means no modifiers
+    pub fn param(self) -> u32 {
+        1 + self.shift as u32 + (self.alt as u32) * 2 + (self.ctrl as u32) * 4 + (self.meta as u32) * 8
+    }
+
+    /// The kitty keyboard protocol modifier parameter, where 1 means no modifiers
+    pub fn kitty_param(self) -> u32 {
+        1 + self.shift as u32 +
+        (self.alt as u32) * 2 +
+        (self.ctrl as u32) * 4 +
+        (self.super_key as u32) * 8 +
+        (self.hyper as u32) * 16 +
+        (self.meta as u32) * 32 +
+        (self.caps_lock as u32) * 64 +
+        (self.num_lock as u32) * 128
+    }
+}
@@ -0,0 +1,140 @@
+use modifiers::Modifiers;
+use State;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    /// No button, for motion without a button held
+    None,
+    Left,
+    Middle,
+    Right,
+    WheelUp,
+    WheelDown,
+    WheelLeft,
+    WheelRight,
+    Back,
+    Forward,
+}
+
+impl MouseButton {
+    fn is_wheel(self) -> bool {
+        matches!(
+            self,
+            MouseButton::WheelUp |
+            MouseButton::WheelDown |
+            MouseButton::WheelLeft |
+            MouseButton::WheelRight
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MouseEventKind {
+    Press,
+    Release,
+    Motion,
+}
+
+/// A mouse event from the host
+#[derive(Copy, Clone, Debug)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub button: MouseButton,
+    /// Column, starting at 0
+    pub x: usize,
+    /// Row, starting at 0
+    pub y: usize,
+    /// Horizontal position in pixels, used by SGR-Pixels mode
+    pub pixel_x: usize,
+    /// Vertical position in pixels, used by SGR-Pixels mode
+    pub pixel_y: usize,
+    pub modifiers: Modifiers,
+}
+
+impl MouseEvent {
+    /// Encode the report for the active mouse modes, if this event should be reported
+    pub fn encode(&self, state: &State) -> Option<Vec<u8>> {
+        let tracking = state.mouse_vt200 || state.mouse_btn || state.mouse_any;
+        let report = match self.kind {
+            MouseEventKind::Press => tracking || state.mouse_x10,
+            // The wheel has no release
+            MouseEventKind::Release => tracking && ! self.button.is_wheel(),
+            MouseEventKind::Motion => state.mouse_any || (state.mouse_btn && self.button != MouseButton::None),
+        };
+        if ! report {
+            return None;
+        }
+
+        // X10 compatibility mode only reports presses of the first three buttons, without modifiers
+        let x10 = ! tracking;
+        let mut code: u32 = match self.button {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+            MouseButton::None => 3,
+            MouseButton::WheelUp => 64,
+            MouseButton::WheelDown => 65,
+            MouseButton::WheelLeft => 66,
+            MouseButton::WheelRight => 67,
+            MouseButton::Back => 128,
+            MouseButton::Forward => 129,
+        };
+        if x10 && code > 2 {
+            return None;
+        }
+
+        let sgr = state.mouse_sgr || state.mouse_sgr_pixels;
+        if self.kind == MouseEventKind::Release && ! sgr {
+            // Only SGR reports which button was released
+            code = 3;
+        }
+        if ! x10 {
+            if self.modifiers.shift {
+                code += 4;
+            }
+            if self.modifiers.alt {
+                code += 8;
+            }
+            if self.modifiers.ctrl {
+                code += 16;
+            }
+        }
+        if self.kind == MouseEventKind::Motion {
+            code += 32;
+        }
+
+        let x = self.x + 1;
+        let y = self.y + 1;
+        let release = self.kind == MouseEventKind::Release;
+        if state.mouse_sgr_pixels {
+            let report = format!("\x1B[<{};{};{}{}", code, self.pixel_x + 1, self.pixel_y + 1, if release { 'm' } else { 'M' });
+            Some(report.into_bytes())
+        } else if state.mouse_sgr {
+            let report = format!("\x1B[<{};{};{}{}", code, x, y, if release { 'm' } else { 'M' });
+            Some(report.into_bytes())
+        } else if state.mouse_rxvt {
+            let report = format!("\x1B[{};{};{}M", code + 32, x, y);
+            Some(report.into_bytes())
+        } else if state.mouse_utf8 {
+            // Values are sent as UTF-8 encoded characters, up to U+07FF
+            let mut report = String::from("\x1B[M");
+            for &value in [code as usize, x, y].iter() {
+                match ::std::char::from_u32(value as u32 + 32) {
+                    Some(c) if value + 32 < 0x800 => report.push(c),
+                    _ => return None,
+                }
+            }
+            Some(report.into_bytes())
+        } else {
+            // Values are sent as single bytes, up to 255
+            let mut report = b"\x1B[M".to_vec();
+            for &value in [code as usize, x, y].iter() {
+                if value + 32 > 255 {
+                    return None;
+                }
+                report.push((value + 32) as u8);
+            }
+            Some(report)
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::cmp;
+
+use color::Color;
+use {Event, Underline};
+
+/// A single character cell
+#[derive(Clone, Debug)]
+pub struct Cell {
+    pub c: char,
+    /// Characters attached to `c` in the same grapheme cluster
+    pub combining: String,
+    pub wide: bool,
+    pub foreground: Color,
+    pub background: Color,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: Underline,
+    pub underline_color: Option<Color>,
+    pub blink: bool,
+    pub rapid_blink: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+    pub overline: bool,
+}
+
+impl Cell {
+    /// An empty cell with the given colors
+    pub fn blank(foreground: Color, background: Color) -> Cell {
+        Cell {
+            c: ' ',
+            combining: String::new(),
+            wide: false,
+            foreground,
+            background,
+            bold: false,
+            dim: false,
+            italic: false,
+            underline: Underline::None,
+            underline_color: None,
+            blink: false,
+            rapid_blink: false,
+            hidden: false,
+            strikethrough: false,
+            overline: false,
+        }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell::blank(Color::Ansi(7), Color::Ansi(0))
+    }
+}
+
+/// A grid of cells, kept up to date from the events produced by `State`
+#[derive(Clone, Debug)]
+pub struct Screen {
+    w: usize,
+    h: usize,
+    cells: Vec<Cell>,
+}
+
+impl Screen {
+    pub fn new(w: usize, h: usize) -> Screen {
+        Screen {
+            w,
+            h,
+            cells: vec![Cell::default(); w * h],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.w
+    }
+
+    pub fn height(&self) -> usize {
+        self.h
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x < self.w && y < self.h {
+            self.cells.get(y * self.w + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[Cell]> {
+        if y < self.h {
+            Some(&self.cells[y * self.w..(y + 1) * self.w])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> ::std::slice::Chunks<'_, Cell> {
+        self.cells.chunks(cmp::max(1, self.w))
+    }
+
+    /// Resize the grid, keeping the contents of the top left corner
+    pub fn resize(&mut self, w: usize, h: usize) {
+        if w == self.w && h == self.h {
+            return;
+        }
+
+        let mut cells = vec![Cell::default(); w * h];
+        for y in 0..cmp::min(h, self.h) {
+            let len = cmp::min(w, self.w);
+            cells[y * w..y * w + len].clone_from_slice(&self.cells[y * self.w..y * self.w + len]);
+        }
+
+        self.w = w;
+        self.h = h;
+        self.cells = cells;
+    }
+
+    /// Apply an event to the grid
+    pub fn event(&mut self, event: &Event) {
+        match *event {
+            Event::Char {
+                x, y, c, grapheme, wide,
+                bold, dim, italic, underline, underline_color, blink, rapid_blink, hidden, strikethrough, overline,
+                color
+            } if x < self.w && y < self.h => {
+                let cell = &mut self.cells[y * self.w + x];
+                cell.c = c;
+                cell.combining.clear();
+                cell.combining.push_str(grapheme.get(c.len_utf8()..).unwrap_or(""));
+                cell.wide = wide;
+                cell.foreground = color;
+                cell.bold = bold;
+                cell.dim = dim;
+                cell.italic = italic;
+                cell.underline = underline;
+                cell.underline_color = underline_color;
+                cell.blink = blink;
+                cell.rapid_blink = rapid_blink;
+                cell.hidden = hidden;
+                cell.strikethrough = strikethrough;
+                cell.overline = overline;
+            },
+            Event::Rect { x, y, w, h, color } => {
+                for row in y..cmp::min(self.h, y.saturating_add(h)) {
+                    for col in x..cmp::min(self.w, x.saturating_add(w)) {
+                        let cell = &mut self.cells[row * self.w + col];
+                        *cell = Cell::blank(cell.foreground, color);
+                    }
+                }
+            },
+            Event::Move { from_x, from_y, to_x, to_y, w, h } => {
+                // Clip the region to both the source and destination
+                let w = cmp::min(w, cmp::min(self.w.saturating_sub(from_x), self.w.saturating_sub(to_x)));
+                let h = cmp::min(h, cmp::min(self.h.saturating_sub(from_y), self.h.saturating_sub(to_y)));
+                if w == 0 || h == 0 {
+                    return;
+                }
+
+                // Copy the source first so that overlapping regions move correctly
+                let mut region = Vec::with_capacity(w * h);
+                for row in from_y..from_y + h {
+                    let start = row * self.w + from_x;
+                    region.extend_from_slice(&self.cells[start..start + w]);
+                }
+
+                for (i, chunk) in region.chunks(w).enumerate() {
+                    let start = (to_y + i) * self.w + to_x;
+                    self.cells[start..start + w].clone_from_slice(chunk);
+                }
+            },
+            Event::Resize { w, h } => {
+                self.resize(w, h);
+            },
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::cmp;
+use std::collections::VecDeque;
+
+use screen::Cell;
+
+/// A ring buffer of lines that were scrolled off the top of the screen
+#[derive(Clone, Debug)]
+pub struct Scrollback {
+    lines: VecDeque<Vec<Cell>>,
+    capacity: usize,
+    offset: usize,
+}
+
+impl Scrollback {
+    pub fn new(capacity: usize) -> Scrollback {
+        Scrollback {
+            lines: VecDeque::new(),
+            capacity,
+            offset: 0,
+        }
+    }
+
+    /// Maximum number of lines kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the maximum number of lines kept, dropping the oldest lines if needed
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.truncate();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Get a line, where 0 is the oldest line
+    pub fn line(&self, i: usize) -> Option<&[Cell]> {
+        self.lines.get(i).map(|line| &line[..])
+    }
+
+    /// Iterate over the lines, from oldest to newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item=&[Cell]> {
+        self.lines.iter().map(|line| &line[..])
+    }
+
+    pub fn push(&mut self, line: Vec<Cell>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.lines.push_back(line);
+        // Keep the viewport on the same content while scrolled back
+        if self.offset > 0 {
+            self.offset += 1;
+        }
+        self.truncate();
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.offset = 0;
+    }
+
+    /// Number of lines the viewport is scrolled back, where 0 shows the screen
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = cmp::min(offset, self.lines.len());
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        let offset = self.offset.saturating_add(lines);
+        self.set_offset(offset);
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        let offset = self.offset.saturating_sub(lines);
+        self.set_offset(offset);
+    }
+
+    fn truncate(&mut self) {
+        while self.lines.len() > self.capacity {
+            self.lines.pop_front();
+        }
+        self.offset = cmp::min(self.offset, self.lines.len());
+    }
+}
@@ -0,0 +1,77 @@
+extern crate ransid;
+
+mod common;
+
+use common::{console, rows};
+
+#[test]
+fn erase_characters() {
+    let console = console(6, 1, b"abcdef\x1B[2G\x1B[2X");
+    assert_eq!(rows(&console), ["a  def"]);
+    assert_eq!(console.state.x, 1);
+}
+
+#[test]
+fn erase_characters_clamped() {
+    let console = console(6, 1, b"abcdef\x1B[5G\x1B[9X");
+    assert_eq!(rows(&console), ["abcd  "]);
+}
+
+#[test]
+fn repeat() {
+    let console = console(6, 2, b"ab\x1B[3b");
+    assert_eq!(rows(&console), ["abbbb ", "      "]);
+}
+
+#[test]
+fn repeat_wraps() {
+    let console = console(4, 2, b"x\x1B[5b");
+    assert_eq!(rows(&console), ["xxxx", "xx  "]);
+}
+
+#[test]
+fn repeat_wide() {
+    let console = console(8, 1, "中\x1B[2b".as_bytes());
+    assert_eq!(rows(&console), ["中 中 中   "]);
+    let screen = console.screen();
+    assert!(screen.cell(0, 0).unwrap().wide);
+    assert!(screen.cell(2, 0).unwrap().wide);
+    assert!(screen.cell(4, 0).unwrap().wide);
+    assert_eq!(console.state.x, 6);
+}
+
+#[test]
+fn repeat_without_character() {
+    let console = console(4, 1, b"\x1B[3b");
+    assert_eq!(rows(&console), ["    "]);
+}
+
+#[test]
+fn insert_column() {
+    let console = console(4, 4, b"abcd\r\nefgh\r\nijkl\r\nmnop\x1B[2;3r\x1B[2;2H\x1B['}");
+    assert_eq!(rows(&console), ["abcd", "e fg", "i jk", "mnop"]);
+}
+
+#[test]
+fn insert_column_at_right_edge() {
+    let console = console(4, 3, b"abcd\r\nefgh\r\nijkl\x1B[2;3r\x1B[2;4H\x1B[3'}");
+    assert_eq!(rows(&console), ["abcd", "efg ", "ijk "]);
+}
+
+#[test]
+fn delete_column() {
+    let console = console(4, 4, b"abcd\r\nefgh\r\nijkl\r\nmnop\x1B[2;3r\x1B[3;2H\x1B['~");
+    assert_eq!(rows(&console), ["abcd", "egh ", "ikl ", "mnop"]);
+}
+
+#[test]
+fn delete_column_at_left_edge() {
+    let console = console(4, 3, b"abcd\r\nefgh\r\nijkl\x1B[2;3r\x1B[2;1H\x1B[9'~");
+    assert_eq!(rows(&console), ["abcd", "    ", "    "]);
+}
+
+#[test]
+fn column_outside_region_ignored() {
+    let console = console(4, 3, b"abcd\r\nefgh\r\nijkl\x1B[2;3r\x1B[1;2H\x1B['}\x1B['~");
+    assert_eq!(rows(&console), ["abcd", "efgh", "ijkl"]);
+}
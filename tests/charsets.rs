@@ -0,0 +1,59 @@
+extern crate ransid;
+
+mod common;
+
+use common::{console, rows};
+
+#[test]
+fn line_drawing() {
+    let console = console(5, 2, b"\x1B(0lqqk\r\n\x1B(Bmqqj");
+    assert_eq!(rows(&console), ["┌──┐ ", "mqqj "]);
+}
+
+#[test]
+fn shift_out_and_in() {
+    let console = console(6, 1, b"\x1B)0x\x0Ex\x0Fx");
+    assert_eq!(rows(&console), ["x│x   "]);
+}
+
+#[test]
+fn locking_shifts() {
+    let console = console(6, 1, b"\x1B*0q\x1Bnq\x1Boq\x0Fq");
+    assert_eq!(rows(&console), ["q─qq  "]);
+}
+
+#[test]
+fn single_shifts() {
+    let console = console(6, 1, b"\x1B+0\x1BNqq\x1BOqq");
+    assert_eq!(rows(&console), ["qq─q  "]);
+}
+
+#[test]
+fn reset_designations() {
+    let console = console(6, 1, b"\x1B(0\x1B)0\x0E\x1BcqQ");
+    assert_eq!(rows(&console), ["qQ    "]);
+}
+
+#[test]
+fn national_without_nrcs_mode() {
+    let console = console(6, 1, b"\x1B(K[]~");
+    assert_eq!(rows(&console), ["[]~   "]);
+}
+
+#[test]
+fn national_with_nrcs_mode() {
+    let console = console(6, 1, b"\x1B[?42h\x1B(K[]~\x1B[?42l~");
+    assert_eq!(rows(&console), ["ÄÜß~  "]);
+}
+
+#[test]
+fn british_without_nrcs_mode() {
+    let console = console(4, 1, b"\x1B(A#a");
+    assert_eq!(rows(&console), ["£a  "]);
+}
+
+#[test]
+fn supplemental_and_technical() {
+    let console = console(6, 1, b"\x1B)<\x1B*>\x1B/A\x0E+W\x1BNW\x1BOq");
+    assert_eq!(rows(&console), ["«ŒΩñ  "]);
+}
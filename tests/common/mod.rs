@@ -0,0 +1,27 @@
+// Helpers shared by the integration tests, not all of which use every helper
+#![allow(dead_code)]
+
+use ransid::{Console, Event};
+
+pub fn console(w: usize, h: usize, data: &[u8]) -> Console {
+    let mut console = Console::new(w, h);
+    console.write(data, |_| ());
+    console
+}
+
+pub fn row(console: &Console, y: usize) -> String {
+    let screen = console.screen();
+    (0..screen.width()).map(|x| screen.cell(x, y).unwrap().c).collect()
+}
+
+pub fn rows(console: &Console) -> Vec<String> {
+    (0..console.screen().height()).map(|y| row(console, y)).collect()
+}
+
+pub fn input(console: &mut Console, data: &[u8]) -> String {
+    let mut input = Vec::new();
+    console.write(data, |event| if let Event::Input { data } = event {
+        input.extend_from_slice(data);
+    });
+    String::from_utf8(input).unwrap()
+}
@@ -0,0 +1,66 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::Console;
+use common::{console, rows};
+
+fn combining(console: &Console, x: usize, y: usize) -> String {
+    console.screen().cell(x, y).unwrap().combining.clone()
+}
+
+#[test]
+fn combining_mark() {
+    let console = console(4, 1, "e\u{301}x".as_bytes());
+    assert_eq!(rows(&console), ["ex  "]);
+    assert_eq!(combining(&console, 0, 0), "\u{301}");
+    assert_eq!(console.state.x, 2);
+}
+
+#[test]
+fn zero_width_joiner_sequence() {
+    let console = console(4, 1, "👨\u{200D}👩\u{200D}👧x".as_bytes());
+    assert_eq!(rows(&console), ["👨 x "]);
+    assert_eq!(combining(&console, 0, 0), "\u{200D}👩\u{200D}👧");
+    assert_eq!(console.state.x, 3);
+}
+
+#[test]
+fn zero_width_joiner_before_letter() {
+    let console = console(4, 1, "a\u{200D}bc".as_bytes());
+    assert_eq!(rows(&console), ["abc "]);
+    assert_eq!(combining(&console, 0, 0), "\u{200D}");
+    assert_eq!(combining(&console, 1, 0), "");
+    assert_eq!(console.state.x, 3);
+}
+
+#[test]
+fn variation_selector() {
+    let console = console(4, 1, "\u{2764}\u{FE0F}x".as_bytes());
+    assert_eq!(rows(&console), ["\u{2764}x  "]);
+    assert_eq!(combining(&console, 0, 0), "\u{FE0F}");
+}
+
+#[test]
+fn emoji_modifier() {
+    let console = console(4, 1, "👍\u{1F3FD}x".as_bytes());
+    assert_eq!(rows(&console), ["👍 x "]);
+    assert_eq!(combining(&console, 0, 0), "\u{1F3FD}");
+}
+
+#[test]
+fn emoji_modifier_after_letter() {
+    let console = console(4, 1, "a\u{1F3FD}".as_bytes());
+    assert_eq!(rows(&console), ["a\u{1F3FD}  "]);
+    assert_eq!(combining(&console, 0, 0), "");
+    assert_eq!(console.state.x, 3);
+}
+
+#[test]
+fn regional_indicator_pairs() {
+    let console = console(6, 1, "🇫🇷🇩🇪".as_bytes());
+    assert_eq!(rows(&console), ["🇫🇩    "]);
+    assert_eq!(combining(&console, 0, 0), "🇷");
+    assert_eq!(combining(&console, 1, 0), "🇪");
+    assert_eq!(console.state.x, 2);
+}
@@ -0,0 +1,195 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::{Console, Key, KeyEvent, KeyEventKind, Modifiers, Underline};
+use common::{console, input};
+
+fn key(console: &Console, key: Key, modifiers: Modifiers) -> Option<String> {
+    console.encode_key(key, modifiers).map(|report| String::from_utf8(report).unwrap())
+}
+
+fn shift() -> Modifiers {
+    Modifiers { shift: true, ..Modifiers::default() }
+}
+
+fn ctrl() -> Modifiers {
+    Modifiers { ctrl: true, ..Modifiers::default() }
+}
+
+fn alt() -> Modifiers {
+    Modifiers { alt: true, ..Modifiers::default() }
+}
+
+#[test]
+fn characters() {
+    let console = console(10, 1, b"");
+    assert_eq!(key(&console, Key::Char('a'), Modifiers::default()), Some("a".to_string()));
+    assert_eq!(key(&console, Key::Char('a'), ctrl()), Some("\x01".to_string()));
+    assert_eq!(key(&console, Key::Char('a'), alt()), Some("\x1Ba".to_string()));
+    assert_eq!(key(&console, Key::Backspace, Modifiers::default()), Some("\x7F".to_string()));
+    assert_eq!(key(&console, Key::Tab, shift()), Some("\x1B[Z".to_string()));
+}
+
+#[test]
+fn cursor_keys() {
+    let mut console = console(10, 1, b"");
+    assert_eq!(key(&console, Key::Up, Modifiers::default()), Some("\x1B[A".to_string()));
+    console.write(b"\x1B[?1h", |_| ());
+    assert_eq!(key(&console, Key::Up, Modifiers::default()), Some("\x1BOA".to_string()));
+    assert_eq!(key(&console, Key::Home, Modifiers::default()), Some("\x1BOH".to_string()));
+    // Modifiers always use the CSI form
+    assert_eq!(key(&console, Key::Up, shift()), Some("\x1B[1;2A".to_string()));
+    console.write(b"\x1B[?1l", |_| ());
+    assert_eq!(key(&console, Key::Left, Modifiers::default()), Some("\x1B[D".to_string()));
+}
+
+#[test]
+fn editing_keys() {
+    let console = console(10, 1, b"");
+    assert_eq!(key(&console, Key::Delete, Modifiers::default()), Some("\x1B[3~".to_string()));
+    assert_eq!(key(&console, Key::PageUp, ctrl()), Some("\x1B[5;5~".to_string()));
+}
+
+#[test]
+fn application_keypad() {
+    let mut console = console(10, 1, b"");
+    assert_eq!(key(&console, Key::Keypad('5'), Modifiers::default()), Some("5".to_string()));
+    assert_eq!(key(&console, Key::KeypadEnter, Modifiers::default()), Some("\r".to_string()));
+    console.write(b"\x1B=", |_| ());
+    assert_eq!(key(&console, Key::Keypad('5'), Modifiers::default()), Some("\x1BOu".to_string()));
+    assert_eq!(key(&console, Key::Keypad('+'), Modifiers::default()), Some("\x1BOk".to_string()));
+    assert_eq!(key(&console, Key::KeypadEnter, Modifiers::default()), Some("\x1BOM".to_string()));
+    console.write(b"\x1B>", |_| ());
+    assert_eq!(key(&console, Key::Keypad('5'), Modifiers::default()), Some("5".to_string()));
+}
+
+#[test]
+fn function_keys() {
+    let console = console(10, 1, b"");
+    assert_eq!(key(&console, Key::F(1), Modifiers::default()), Some("\x1BOP".to_string()));
+    assert_eq!(key(&console, Key::F(4), ctrl()), Some("\x1B[1;5S".to_string()));
+    assert_eq!(key(&console, Key::F(5), Modifiers::default()), Some("\x1B[15~".to_string()));
+    assert_eq!(key(&console, Key::F(5), shift()), Some("\x1B[15;2~".to_string()));
+    assert_eq!(key(&console, Key::F(12), alt()), Some("\x1B[24;3~".to_string()));
+    // F13 to F24 are shifted F1 to F12
+    assert_eq!(key(&console, Key::F(13), Modifiers::default()), Some("\x1B[1;2P".to_string()));
+    assert_eq!(key(&console, Key::F(13), ctrl()), Some("\x1B[1;6P".to_string()));
+    assert_eq!(key(&console, Key::F(24), Modifiers::default()), Some("\x1B[24;2~".to_string()));
+    assert_eq!(key(&console, Key::F(25), Modifiers::default()), None);
+}
+
+fn key_event(console: &Console, event: KeyEvent) -> Option<String> {
+    console.encode_key_event(&event).map(|report| String::from_utf8(report).unwrap())
+}
+
+fn kind(key: Key, modifiers: Modifiers, kind: KeyEventKind) -> KeyEvent {
+    KeyEvent { kind, ..KeyEvent::new(key, modifiers) }
+}
+
+#[test]
+fn kitty_flag_stack() {
+    let mut console = console(10, 1, b"");
+    assert_eq!(input(&mut console, b"\x1B[?u"), "\x1B[?0u");
+    assert_eq!(input(&mut console, b"\x1B[>1u\x1B[?u"), "\x1B[?1u");
+    assert_eq!(input(&mut console, b"\x1B[>3u\x1B[?u"), "\x1B[?3u");
+    assert_eq!(input(&mut console, b"\x1B[<u\x1B[?u"), "\x1B[?1u");
+    assert_eq!(input(&mut console, b"\x1B[=2;2u\x1B[?u"), "\x1B[?3u");
+    assert_eq!(input(&mut console, b"\x1B[=1;3u\x1B[?u"), "\x1B[?2u");
+    assert_eq!(input(&mut console, b"\x1B[=5u\x1B[?u"), "\x1B[?5u");
+    assert_eq!(input(&mut console, b"\x1B[<5u\x1B[?u"), "\x1B[?0u");
+}
+
+#[test]
+fn kitty_flags_per_screen() {
+    let mut console = console(10, 1, b"\x1B[>1u\x1B[?1049h");
+    assert_eq!(input(&mut console, b"\x1B[?u"), "\x1B[?0u");
+    assert_eq!(input(&mut console, b"\x1B[?1049l\x1B[?u"), "\x1B[?1u");
+}
+
+#[test]
+fn kitty_disambiguate() {
+    let mut console = console(10, 1, b"");
+    assert_eq!(key(&console, Key::Char('i'), ctrl()), key(&console, Key::Tab, Modifiers::default()));
+    console.write(b"\x1B[>1u", |_| ());
+    assert_eq!(key(&console, Key::Char('i'), ctrl()), Some("\x1B[105;5u".to_string()));
+    assert_eq!(key(&console, Key::Tab, Modifiers::default()), Some("\t".to_string()));
+    assert_eq!(key(&console, Key::Char('a'), Modifiers::default()), Some("a".to_string()));
+    assert_eq!(key(&console, Key::Escape, Modifiers::default()), Some("\x1B[27u".to_string()));
+    assert_eq!(key(&console, Key::Up, Modifiers::default()), Some("\x1B[A".to_string()));
+}
+
+#[test]
+fn kitty_event_types() {
+    let mut console = console(10, 1, b"\x1B[>1u");
+    assert_eq!(key_event(&console, kind(Key::Char('a'), Modifiers::default(), KeyEventKind::Release)), None);
+    assert_eq!(key_event(&console, kind(Key::Up, Modifiers::default(), KeyEventKind::Repeat)), Some("\x1B[A".to_string()));
+    console.write(b"\x1B[=3u", |_| ());
+    assert_eq!(key_event(&console, kind(Key::Up, Modifiers::default(), KeyEventKind::Press)), Some("\x1B[A".to_string()));
+    assert_eq!(key_event(&console, kind(Key::Up, Modifiers::default(), KeyEventKind::Repeat)), Some("\x1B[1;1:2A".to_string()));
+    assert_eq!(key_event(&console, kind(Key::Char('a'), Modifiers::default(), KeyEventKind::Release)), Some("\x1B[97;1:3u".to_string()));
+    assert_eq!(key_event(&console, kind(Key::Char('a'), ctrl(), KeyEventKind::Repeat)), Some("\x1B[97;5:2u".to_string()));
+    // Enter, Tab and Backspace releases need all keys as escape codes
+    assert_eq!(key_event(&console, kind(Key::Enter, Modifiers::default(), KeyEventKind::Release)), None);
+    console.write(b"\x1B[=11u", |_| ());
+    assert_eq!(key_event(&console, kind(Key::Enter, Modifiers::default(), KeyEventKind::Release)), Some("\x1B[13;1:3u".to_string()));
+}
+
+#[test]
+fn kitty_alternate_keys() {
+    let console = console(10, 1, b"\x1B[>5u");
+    let ctrl_shift = Modifiers { ctrl: true, shift: true, ..Modifiers::default() };
+    assert_eq!(key(&console, Key::Char('A'), ctrl_shift), Some("\x1B[97:65;6u".to_string()));
+    let event = KeyEvent { base_layout_key: Some('a'), ..KeyEvent::new(Key::Char('ф'), ctrl()) };
+    assert_eq!(key_event(&console, event), Some("\x1B[1092::97;5u".to_string()));
+    let event = KeyEvent { unshifted_key: Some('1'), ..KeyEvent::new(Key::Char('!'), ctrl_shift) };
+    assert_eq!(key_event(&console, event), Some("\x1B[49:33;6u".to_string()));
+}
+
+#[test]
+fn kitty_all_keys() {
+    let console = console(10, 1, b"\x1B[>9u");
+    assert_eq!(key(&console, Key::Char('a'), Modifiers::default()), Some("\x1B[97u".to_string()));
+    assert_eq!(key(&console, Key::Enter, Modifiers::default()), Some("\x1B[13u".to_string()));
+    assert_eq!(key(&console, Key::F(13), Modifiers::default()), Some("\x1B[57376u".to_string()));
+    assert_eq!(key(&console, Key::Keypad('0'), Modifiers::default()), Some("\x1B[57399u".to_string()));
+}
+
+#[test]
+fn kitty_text() {
+    let console = console(10, 1, b"\x1B[>25u");
+    assert_eq!(key(&console, Key::Char('a'), Modifiers::default()), Some("\x1B[97;1;97u".to_string()));
+    assert_eq!(key(&console, Key::Char('A'), shift()), Some("\x1B[97;2;65u".to_string()));
+    assert_eq!(key(&console, Key::Char('a'), ctrl()), Some("\x1B[97;5u".to_string()));
+    let event = KeyEvent { text: Some("é".to_string()), ..KeyEvent::new(Key::Char('e'), Modifiers::default()) };
+    assert_eq!(key_event(&console, event), Some("\x1B[101;1;233u".to_string()));
+}
+
+#[test]
+fn modify_other_keys_is_not_sgr() {
+    let mut console = console(10, 1, b"\x1B[>4;2m");
+    assert_eq!(console.state.modify_other_keys, 2);
+    assert_eq!(console.state.underline, Underline::None);
+    assert!(! console.state.dim);
+    assert_eq!(input(&mut console, b"\x1B[?4m"), "\x1B[>4;2m");
+    assert_eq!(input(&mut console, b"\x1B[>4n\x1B[?4m"), "\x1B[>4;0m");
+}
+
+#[test]
+fn modify_other_keys() {
+    let mut console = console(10, 1, b"\x1B[>4;1m");
+    assert_eq!(key(&console, Key::Char('a'), ctrl()), Some("\x01".to_string()));
+    assert_eq!(key(&console, Key::Char('1'), ctrl()), Some("\x1B[27;5;49~".to_string()));
+    console.write(b"\x1B[>4;2m", |_| ());
+    assert_eq!(key(&console, Key::Char('a'), ctrl()), Some("\x1B[27;5;97~".to_string()));
+    assert_eq!(key(&console, Key::Char('a'), alt()), Some("\x1B[27;3;97~".to_string()));
+    assert_eq!(key(&console, Key::Enter, Modifiers::default()), Some("\r".to_string()));
+}
+
+#[test]
+fn format_other_keys() {
+    let mut console = console(10, 1, b"\x1B[>4;2m");
+    console.state.format_other_keys = true;
+    assert_eq!(key(&console, Key::Char('a'), ctrl()), Some("\x1B[97;5u".to_string()));
+    assert_eq!(key(&console, Key::Tab, shift()), Some("\x1B[9;2u".to_string()));
+}
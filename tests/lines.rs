@@ -0,0 +1,75 @@
+extern crate ransid;
+
+mod common;
+
+use common::{console, rows};
+
+#[test]
+fn insert_line() {
+    let console = console(3, 4, b"aaa\r\nbbb\r\nccc\r\nddd\x1B[2;2H\x1B[L");
+    assert_eq!(rows(&console), ["aaa", "   ", "bbb", "ccc"]);
+    assert_eq!((console.state.x, console.state.y), (0, 1));
+}
+
+#[test]
+fn insert_lines_clamped() {
+    let console = console(3, 4, b"aaa\r\nbbb\r\nccc\r\nddd\x1B[3H\x1B[9L");
+    assert_eq!(rows(&console), ["aaa", "bbb", "   ", "   "]);
+}
+
+#[test]
+fn delete_line() {
+    let console = console(3, 4, b"aaa\r\nbbb\r\nccc\r\nddd\x1B[2;3H\x1B[M");
+    assert_eq!(rows(&console), ["aaa", "ccc", "ddd", "   "]);
+    assert_eq!((console.state.x, console.state.y), (0, 1));
+}
+
+#[test]
+fn delete_lines_clamped() {
+    let console = console(3, 4, b"aaa\r\nbbb\r\nccc\r\nddd\x1B[2H\x1B[9M");
+    assert_eq!(rows(&console), ["aaa", "   ", "   ", "   "]);
+}
+
+#[test]
+fn insert_line_within_margins() {
+    let console = console(3, 5, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee\x1B[2;4r\x1B[3H\x1B[L");
+    assert_eq!(rows(&console), ["aaa", "bbb", "   ", "ccc", "eee"]);
+}
+
+#[test]
+fn delete_line_within_margins() {
+    let console = console(3, 5, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee\x1B[2;4r\x1B[2H\x1B[2M");
+    assert_eq!(rows(&console), ["aaa", "ddd", "   ", "   ", "eee"]);
+}
+
+#[test]
+fn outside_margins_ignored() {
+    let console = console(3, 5, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee\x1B[2;4r\x1B[5;2H\x1B[L\x1B[M");
+    assert_eq!(rows(&console), ["aaa", "bbb", "ccc", "ddd", "eee"]);
+    assert_eq!((console.state.x, console.state.y), (1, 4));
+}
+
+#[test]
+fn scroll_region() {
+    let console = console(3, 5, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee\x1B[2;4r\x1B[4H\n");
+    assert_eq!(rows(&console), ["aaa", "ccc", "ddd", "   ", "eee"]);
+}
+
+#[test]
+fn scroll_up_clamped() {
+    let console = console(3, 5, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee\x1B[2;4r\x1B[9S");
+    assert_eq!(rows(&console), ["aaa", "   ", "   ", "   ", "eee"]);
+}
+
+#[test]
+fn scroll_down_clamped() {
+    let console = console(3, 5, b"aaa\r\nbbb\r\nccc\r\nddd\r\neee\x1B[2;4r\x1B[9T");
+    assert_eq!(rows(&console), ["aaa", "   ", "   ", "   ", "eee"]);
+}
+
+#[test]
+fn line_feed_below_region() {
+    // Scrolls by more rows than the region has
+    let console = console(3, 10, b"\x1B[1;3r\x1B[10H\n");
+    assert_eq!(rows(&console)[9], "   ");
+}
@@ -0,0 +1,122 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::Console;
+use common::{console, input, rows};
+
+// Fill a console with rows of letters, then set the left and right margins to columns 2 to 4
+fn filled(w: usize, h: usize, data: &[u8]) -> Console {
+    let mut console = Console::new(w, h);
+    for y in 0..h {
+        let c = b'a' + y as u8;
+        console.write(&vec![c; w], |_| ());
+        if y + 1 < h {
+            console.write(b"\r\n", |_| ());
+        }
+    }
+    console.write(b"\x1B[?69h\x1B[2;4s", |_| ());
+    console.write(data, |_| ());
+    console
+}
+
+#[test]
+fn margins_set() {
+    let console = console(6, 2, b"\x1B[?69h\x1B[2;4s");
+    assert_eq!((console.state.left_margin, console.state.right_margin), (1, 3));
+    assert_eq!((console.state.x, console.state.y), (0, 0));
+}
+
+#[test]
+fn save_cursor_without_margin_mode() {
+    let console = console(6, 2, b"\x1B[2;3H\x1B[2;4s\x1B[H\x1B[u");
+    assert_eq!((console.state.left_margin, console.state.right_margin), (0, 5));
+    assert_eq!((console.state.x, console.state.y), (2, 1));
+}
+
+#[test]
+fn margins_reset_with_mode() {
+    let console = console(6, 2, b"\x1B[?69h\x1B[2;4s\x1B[?69l");
+    assert_eq!((console.state.left_margin, console.state.right_margin), (0, 5));
+}
+
+#[test]
+fn wrap_at_right_margin() {
+    let console = filled(6, 3, b"\x1B[1;2HXYZUV");
+    assert_eq!(rows(&console), ["aXYZaa", "bUVbbb", "cccccc"]);
+}
+
+#[test]
+fn wrap_at_right_margin_after_escape() {
+    let console = filled(6, 3, b"\x1B[1;2HXYZ\x1B[31mU");
+    assert_eq!(rows(&console), ["aXYZaa", "bUbbbb", "cccccc"]);
+}
+
+#[test]
+fn wrap_right_of_margins() {
+    let console = filled(6, 3, b"\x1B[1;5HXYZ");
+    assert_eq!(rows(&console), ["aaaaXY", "Zbbbbb", "cccccc"]);
+}
+
+#[test]
+fn line_feed_scrolls_inside_margins() {
+    let console = filled(6, 3, b"\x1B[3;2H\n");
+    assert_eq!(rows(&console), ["abbbaa", "bcccbb", "c   cc"]);
+    assert!(console.scrollback().is_empty());
+}
+
+#[test]
+fn insert_line_inside_margins() {
+    let console = filled(6, 3, b"\x1B[2;3H\x1B[L");
+    assert_eq!(rows(&console), ["aaaaaa", "b   bb", "cbbbcc"]);
+    assert_eq!(console.state.x, 1);
+}
+
+#[test]
+fn delete_line_inside_margins() {
+    let console = filled(6, 3, b"\x1B[1;3H\x1B[M");
+    assert_eq!(rows(&console), ["abbbaa", "bcccbb", "c   cc"]);
+}
+
+#[test]
+fn insert_line_outside_margins_ignored() {
+    let console = filled(6, 3, b"\x1B[2;6H\x1B[L");
+    assert_eq!(rows(&console), ["aaaaaa", "bbbbbb", "cccccc"]);
+}
+
+#[test]
+fn delete_character_inside_margins() {
+    let console = filled(6, 1, b"\x1B[1;2H\x1B[P");
+    assert_eq!(rows(&console), ["aaa aa"]);
+}
+
+#[test]
+fn origin_mode() {
+    let mut console = filled(6, 3, b"\x1B[?6h\x1B[1;2H");
+    assert_eq!(console.state.x, 2);
+    console.write(b"\x1B[9G", |_| ());
+    assert_eq!(console.state.x, 3);
+    console.write(b"\x1B[2G", |_| ());
+    assert_eq!(console.state.x, 2);
+    assert_eq!(input(&mut console, b"\x1B[6n"), "\x1B[1;2R");
+}
+
+#[test]
+fn cursor_movement_stops_at_margins() {
+    let mut console = filled(6, 1, b"\x1B[3G\x1B[9C");
+    assert_eq!(console.state.x, 3);
+    console.write(b"\x1B[9D", |_| ());
+    assert_eq!(console.state.x, 1);
+    console.write(b"\x1B[6G\x1B[9C", |_| ());
+    assert_eq!(console.state.x, 5);
+    console.write(b"\x1B[1G\x1B[9D", |_| ());
+    assert_eq!(console.state.x, 0);
+}
+
+#[test]
+fn backspace_stops_at_left_margin() {
+    let mut console = console(6, 1, b"\x1B[?69h\x1B[3;5s\x1B[3G\x08\x08");
+    assert_eq!(console.state.x, 2);
+    console.write(b"\x1B[2G\x08\x08", |_| ());
+    assert_eq!(console.state.x, 0);
+}
@@ -0,0 +1,65 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::{Console, Key, Modifiers};
+use common::{console, rows};
+
+#[test]
+fn insert_mode() {
+    let console = console(6, 1, b"abcd\x1B[2G\x1B[4hXY");
+    assert_eq!(rows(&console), ["aXYbcd"]);
+    assert!(console.state.insert);
+}
+
+#[test]
+fn insert_mode_drops_last_column() {
+    let console = console(4, 1, b"abcd\x1B[1G\x1B[4hX");
+    assert_eq!(rows(&console), ["Xabc"]);
+}
+
+#[test]
+fn replace_mode() {
+    let console = console(6, 1, b"abcd\x1B[4h\x1B[4l\x1B[2GXY");
+    assert_eq!(rows(&console), ["aXYd  "]);
+    assert!(! console.state.insert);
+}
+
+#[test]
+fn private_mode_is_not_insert_mode() {
+    // DECSCLM (smooth scroll) shares the number of IRM
+    let console = console(6, 1, b"abcd\x1B[?4h\x1B[2GXY");
+    assert_eq!(rows(&console), ["aXYd  "]);
+    assert!(! console.state.insert);
+}
+
+#[test]
+fn line_feed() {
+    let console = console(4, 2, b"ab\ncd");
+    assert_eq!(rows(&console), ["ab  ", "  cd"]);
+}
+
+#[test]
+fn newline_mode() {
+    let console = console(4, 4, b"\x1B[20hab\ncd\x0Bef\x0Cg");
+    assert_eq!(rows(&console), ["ab  ", "cd  ", "ef  ", "g   "]);
+    assert!(console.state.newline);
+}
+
+#[test]
+fn newline_mode_enter() {
+    let mut console = console(4, 1, b"");
+    assert_eq!(console.encode_key(Key::Enter, Modifiers::default()), Some(b"\r".to_vec()));
+    console.write(b"\x1B[20h", |_| ());
+    assert_eq!(console.encode_key(Key::Enter, Modifiers::default()), Some(b"\r\n".to_vec()));
+    console.write(b"\x1B[20l", |_| ());
+    assert_eq!(console.encode_key(Key::Enter, Modifiers::default()), Some(b"\r".to_vec()));
+}
+
+#[test]
+fn implicit_carriage_return() {
+    let mut console = Console::new(4, 2);
+    console.state.implicit_carriage_return = true;
+    console.write(b"ab\ncd", |_| ());
+    assert_eq!(rows(&console), ["ab  ", "cd  "]);
+}
@@ -0,0 +1,96 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::{Modifiers, MouseButton, MouseEvent, MouseEventKind};
+use common::console;
+
+fn event(kind: MouseEventKind, button: MouseButton, x: usize, y: usize) -> MouseEvent {
+    MouseEvent {
+        kind,
+        button,
+        x,
+        y,
+        pixel_x: x * 10,
+        pixel_y: y * 20,
+        modifiers: Modifiers::default(),
+    }
+}
+
+fn press(x: usize, y: usize) -> MouseEvent {
+    event(MouseEventKind::Press, MouseButton::Left, x, y)
+}
+
+fn encode(modes: &[u8], event: MouseEvent) -> Option<String> {
+    let console = console(10, 10, modes);
+    console.encode_mouse(&event).map(|report| String::from_utf8(report).unwrap())
+}
+
+#[test]
+fn disabled() {
+    assert_eq!(encode(b"", press(0, 0)), None);
+}
+
+#[test]
+fn x10_presses_only() {
+    let mut ctrl = press(0, 0);
+    ctrl.modifiers.ctrl = true;
+    assert_eq!(encode(b"\x1B[?9h", press(4, 2)), Some("\x1B[M %#".to_string()));
+    assert_eq!(encode(b"\x1B[?9h", ctrl), Some("\x1B[M !!".to_string()));
+    assert_eq!(encode(b"\x1B[?9h", event(MouseEventKind::Release, MouseButton::Left, 0, 0)), None);
+    assert_eq!(encode(b"\x1B[?9h", event(MouseEventKind::Press, MouseButton::WheelUp, 0, 0)), None);
+    assert_eq!(encode(b"\x1B[?9h", event(MouseEventKind::Motion, MouseButton::Left, 0, 0)), None);
+}
+
+#[test]
+fn vt200() {
+    let mut ctrl = press(0, 0);
+    ctrl.modifiers.ctrl = true;
+    assert_eq!(encode(b"\x1B[?1000h", ctrl), Some("\x1B[M0!!".to_string()));
+    assert_eq!(encode(b"\x1B[?1000h", event(MouseEventKind::Release, MouseButton::Right, 0, 0)), Some("\x1B[M#!!".to_string()));
+    assert_eq!(encode(b"\x1B[?1000h", event(MouseEventKind::Release, MouseButton::WheelUp, 0, 0)), None);
+    assert_eq!(encode(b"\x1B[?1000h", event(MouseEventKind::Motion, MouseButton::Left, 0, 0)), None);
+}
+
+#[test]
+fn button_event_motion() {
+    assert_eq!(encode(b"\x1B[?1002h", event(MouseEventKind::Motion, MouseButton::Left, 1, 0)), Some("\x1B[M@\"!".to_string()));
+    assert_eq!(encode(b"\x1B[?1002h", event(MouseEventKind::Motion, MouseButton::None, 1, 0)), None);
+}
+
+#[test]
+fn any_event_motion() {
+    assert_eq!(encode(b"\x1B[?1003h", event(MouseEventKind::Motion, MouseButton::None, 1, 0)), Some("\x1B[MC\"!".to_string()));
+    assert_eq!(encode(b"\x1B[?1003h", event(MouseEventKind::Motion, MouseButton::Left, 1, 0)), Some("\x1B[M@\"!".to_string()));
+}
+
+#[test]
+fn sgr() {
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1006h", press(4, 2)), Some("\x1B[<0;5;3M".to_string()));
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1006h", event(MouseEventKind::Release, MouseButton::Right, 4, 2)), Some("\x1B[<2;5;3m".to_string()));
+}
+
+#[test]
+fn sgr_pixels() {
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1016h", press(4, 2)), Some("\x1B[<0;41;41M".to_string()));
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1016h", event(MouseEventKind::Release, MouseButton::Left, 4, 2)), Some("\x1B[<0;41;41m".to_string()));
+}
+
+#[test]
+fn urxvt() {
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1015h", press(4, 2)), Some("\x1B[32;5;3M".to_string()));
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1015h", press(400, 2)), Some("\x1B[32;401;3M".to_string()));
+}
+
+#[test]
+fn utf8() {
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1005h", press(300, 2)), Some("\x1B[M \u{14D}#".to_string()));
+    assert_eq!(encode(b"\x1B[?1000h\x1B[?1005h", press(2015, 0)), None);
+}
+
+#[test]
+fn out_of_range() {
+    let console = console(10, 10, b"\x1B[?1000h");
+    assert_eq!(console.encode_mouse(&press(222, 0)), Some(b"\x1B[M \xFF!".to_vec()));
+    assert_eq!(console.encode_mouse(&press(223, 0)), None);
+}
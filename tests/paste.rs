@@ -0,0 +1,45 @@
+extern crate ransid;
+
+mod common;
+
+use common::console;
+
+fn paste(bracketed: bool, text: &str) -> String {
+    let console = console(10, 1, if bracketed { b"\x1B[?2004h" } else { b"" });
+    String::from_utf8(console.encode_paste(text)).unwrap()
+}
+
+#[test]
+fn bracketed() {
+    assert_eq!(paste(true, "ls"), "\x1B[200~ls\x1B[201~");
+}
+
+#[test]
+fn not_bracketed() {
+    assert_eq!(paste(false, "ls\x1B[201~"), "ls");
+}
+
+#[test]
+fn end_marker_removed() {
+    assert_eq!(paste(true, "a\x1B[201~rm -rf ~\n"), "\x1B[200~arm -rf ~\r\x1B[201~");
+}
+
+#[test]
+fn nested_end_marker() {
+    assert_eq!(paste(true, "\x1B[20\x1B[201~1~"), "\x1B[200~[201~\x1B[201~");
+}
+
+#[test]
+fn c1_controls_removed() {
+    assert_eq!(paste(true, "a\u{9B}201~b\u{85}"), "\x1B[200~a201~b\x1B[201~");
+}
+
+#[test]
+fn line_endings() {
+    assert_eq!(paste(false, "a\r\nb\nc\rd"), "a\rb\rc\rd");
+}
+
+#[test]
+fn tabs_kept() {
+    assert_eq!(paste(true, "a\tb\x07\x08"), "\x1B[200~a\tb\x1B[201~");
+}
@@ -0,0 +1,121 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::Color;
+use common::{console, input};
+
+#[test]
+fn request_mode_set_and_reset() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[4$p"), "\x1B[4;2$y");
+    assert_eq!(input(&mut console, b"\x1B[4h\x1B[4$p"), "\x1B[4;1$y");
+    assert_eq!(input(&mut console, b"\x1B[?7$p"), "\x1B[?7;1$y");
+    assert_eq!(input(&mut console, b"\x1B[?7l\x1B[?7$p"), "\x1B[?7;2$y");
+    assert_eq!(input(&mut console, b"\x1B[?2004h\x1B[?2004$p"), "\x1B[?2004;1$y");
+}
+
+#[test]
+fn request_mode_permanent() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[12$p"), "\x1B[12;3$y");
+    assert_eq!(input(&mut console, b"\x1B[?2$p"), "\x1B[?2;3$y");
+    assert_eq!(input(&mut console, b"\x1B[2$p"), "\x1B[2;4$y");
+    assert_eq!(input(&mut console, b"\x1B[?4$p"), "\x1B[?4;4$y");
+    // Permanent modes cannot be changed
+    assert_eq!(input(&mut console, b"\x1B[2h\x1B[2$p"), "\x1B[2;4$y");
+}
+
+#[test]
+fn request_mode_unknown() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[?9999$p"), "\x1B[?9999;0$y");
+    // DEC private modes are not ANSI modes
+    assert_eq!(input(&mut console, b"\x1B[7$p"), "\x1B[7;0$y");
+    assert_eq!(input(&mut console, b"\x1B[?20$p"), "\x1B[?20;0$y");
+}
+
+#[test]
+fn primary_device_attributes() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[c"), "\x1B[?62;9;22c");
+    assert_eq!(input(&mut console, b"\x1B[0c"), "\x1B[?62;9;22c");
+    console.state.identity.level = 64;
+    console.state.identity.features = vec![1, 4];
+    assert_eq!(input(&mut console, b"\x1B[c"), "\x1B[?64;1;4c");
+}
+
+#[test]
+fn secondary_device_attributes() {
+    let mut console = console(10, 5, b"");
+    console.state.identity.terminal_type = 41;
+    console.state.identity.version = 379;
+    assert_eq!(input(&mut console, b"\x1B[>c"), "\x1B[>41;379;0c");
+    assert_eq!(input(&mut console, b"\x1B[>0c"), "\x1B[>41;379;0c");
+}
+
+#[test]
+fn tertiary_device_attributes() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[=c"), "\x1BP!|00000000\x1B\\");
+    console.state.identity.unit_id = 0xABCD;
+    assert_eq!(input(&mut console, b"\x1B[=0c"), "\x1BP!|0000ABCD\x1B\\");
+}
+
+#[test]
+fn xtversion() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[>q"), format!("\x1BP>|ransid({})\x1B\\", env!("CARGO_PKG_VERSION")));
+    console.state.identity.name = "example(1.0)".to_string();
+    assert_eq!(input(&mut console, b"\x1B[>0q"), "\x1BP>|example(1.0)\x1B\\");
+}
+
+#[test]
+fn operating_status() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[5n"), "\x1B[0n");
+}
+
+#[test]
+fn cursor_position_report() {
+    let mut console = console(10, 5, b"\x1B[3;4H");
+    assert_eq!(input(&mut console, b"\x1B[6n"), "\x1B[3;4R");
+}
+
+#[test]
+fn cursor_position_report_origin_mode() {
+    let mut console = console(10, 5, b"\x1B[2;4r\x1B[?69h\x1B[3;8s\x1B[?6h\x1B[2;4H");
+    assert_eq!((console.state.x, console.state.y), (5, 2));
+    assert_eq!(input(&mut console, b"\x1B[6n"), "\x1B[2;4R");
+    assert_eq!(input(&mut console, b"\x1B[?6l\x1B[6n"), "\x1B[1;1R");
+}
+
+#[test]
+fn extended_cursor_position_report() {
+    let mut console = console(10, 5, b"\x1B[3;4H");
+    assert_eq!(input(&mut console, b"\x1B[?6n"), "\x1B[?3;4;1R");
+    assert_eq!(input(&mut console, b"\x1B[2;4r\x1B[?6h\x1B[?6n"), "\x1B[?1;1;1R");
+}
+
+#[test]
+fn private_device_status() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[?15n"), "\x1B[?13n");
+    assert_eq!(input(&mut console, b"\x1B[?25n"), "\x1B[?20n");
+    assert_eq!(input(&mut console, b"\x1B[?26n"), "\x1B[?27;1;0;0n");
+    assert_eq!(input(&mut console, b"\x1B[?53n"), "\x1B[?50n");
+}
+
+#[test]
+fn color_scheme() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[?996n"), "\x1B[?997;1n");
+    console.state.background_default = Color::TrueColor(0xFF, 0xFF, 0xFF);
+    assert_eq!(input(&mut console, b"\x1B[?996n"), "\x1B[?997;2n");
+}
+
+#[test]
+fn unknown_status_ignored() {
+    let mut console = console(10, 5, b"");
+    assert_eq!(input(&mut console, b"\x1B[7n\x1B[?7n"), "");
+}
@@ -0,0 +1,121 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::Event;
+use common::{console, rows};
+
+fn moved(data: &[u8], from: (usize, usize), to: (usize, usize), w: usize, h: usize) -> Vec<String> {
+    let mut console = console(6, 3, data);
+    console.state.screen.event(&Event::Move {
+        from_x: from.0,
+        from_y: from.1,
+        to_x: to.0,
+        to_y: to.1,
+        w,
+        h,
+    });
+    rows(&console)
+}
+
+#[test]
+fn move_right_overlapping() {
+    assert_eq!(moved(b"abcdef", (0, 0), (2, 0), 4, 1), ["ababcd", "      ", "      "]);
+}
+
+#[test]
+fn move_left_overlapping() {
+    assert_eq!(moved(b"abcdef", (2, 0), (0, 0), 4, 1), ["cdefef", "      ", "      "]);
+}
+
+#[test]
+fn move_down_overlapping() {
+    assert_eq!(moved(b"aaaaaa\r\nbbbbbb\r\ncccccc", (0, 0), (0, 1), 6, 2), ["aaaaaa", "aaaaaa", "bbbbbb"]);
+}
+
+#[test]
+fn move_up_overlapping() {
+    assert_eq!(moved(b"aaaaaa\r\nbbbbbb\r\ncccccc", (0, 1), (0, 0), 6, 2), ["bbbbbb", "cccccc", "cccccc"]);
+}
+
+#[test]
+fn move_diagonal_overlapping() {
+    assert_eq!(moved(b"abcdef\r\nghijkl\r\nmnopqr", (0, 0), (1, 1), 3, 2), ["abcdef", "gabckl", "mghiqr"]);
+}
+
+#[test]
+fn move_clipped() {
+    assert_eq!(moved(b"abcdef", (4, 0), (0, 0), 9, 9), ["efcdef", "      ", "      "]);
+    assert_eq!(moved(b"abcdef", (0, 0), (4, 2), 9, 9), ["abcdef", "      ", "    ab"]);
+}
+
+#[test]
+fn move_keeps_attributes() {
+    let mut console = console(6, 1, b"\x1B[1ma\x1B[0mb");
+    console.state.screen.event(&Event::Move { from_x: 0, from_y: 0, to_x: 1, to_y: 0, w: 2, h: 1 });
+    let screen = console.screen();
+    assert!(screen.cell(1, 0).unwrap().bold);
+    assert!(! screen.cell(2, 0).unwrap().bold);
+    assert_eq!(screen.cell(2, 0).unwrap().c, 'b');
+}
+
+#[test]
+fn alternate_screen_save_cursor() {
+    let mut console = console(4, 2, b"main\x1B[1;3H\x1B[?1049h");
+    assert!(console.state.alternate);
+    assert_eq!(rows(&console), ["    ", "    "]);
+    console.write(b"\x1B[2;1Halt", |_| ());
+    assert_eq!(rows(&console), ["    ", "alt "]);
+    console.write(b"\x1B[?1049l", |_| ());
+    assert!(! console.state.alternate);
+    assert_eq!(rows(&console), ["main", "    "]);
+    assert_eq!((console.state.x, console.state.y), (2, 0));
+}
+
+#[test]
+fn alternate_screen_cleared_on_entering_1049() {
+    let console = console(4, 2, b"\x1B[?47hold\x1B[?47lmain\x1B[?1049h");
+    assert_eq!(rows(&console), ["    ", "    "]);
+}
+
+#[test]
+fn alternate_screen_cleared_on_leaving_1047() {
+    let mut console = console(4, 2, b"main\x1B[?1047h\x1B[Halt");
+    assert_eq!(rows(&console), ["alt ", "    "]);
+    // The cursor is not saved or restored
+    console.write(b"\x1B[?1047l", |_| ());
+    assert_eq!(rows(&console), ["main", "    "]);
+    assert_eq!((console.state.x, console.state.y), (3, 0));
+    console.write(b"\x1B[?47h", |_| ());
+    assert_eq!(rows(&console), ["    ", "    "]);
+}
+
+#[test]
+fn alternate_screen_legacy_not_cleared() {
+    let mut console = console(4, 2, b"main\x1B[?47h\x1B[Halt\x1B[?47l");
+    assert_eq!(rows(&console), ["main", "    "]);
+    console.write(b"\x1B[?47h", |_| ());
+    assert_eq!(rows(&console), ["alt ", "    "]);
+}
+
+#[test]
+fn save_cursor_mode() {
+    let console = console(4, 3, b"\x1B[3;3H\x1B[?1048h\x1B[H\x1B[?1048l");
+    assert_eq!((console.state.x, console.state.y), (2, 2));
+}
+
+#[test]
+fn saved_cursor_per_screen() {
+    let console = console(4, 3, b"\x1B[1;2H\x1B7\x1B[?47h\x1B[3;4H\x1B7\x1B[?47l\x1B8");
+    assert_eq!((console.state.x, console.state.y), (1, 0));
+}
+
+#[test]
+fn switch_emits_redraw() {
+    let mut console = console(4, 2, b"");
+    let mut redraws = 0;
+    console.write(b"\x1B[?1049h\x1B[?1049h\x1B[?1049l", |event| if let Event::Redraw = event {
+        redraws += 1;
+    });
+    assert_eq!(redraws, 2);
+}
@@ -0,0 +1,140 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::{Color, Underline};
+use common::console;
+
+#[test]
+fn attributes() {
+    let console = console(4, 1, b"\x1B[1;2;3;4;5;6;7;8;9;53m");
+    let state = &console.state;
+    assert!(state.bold && state.dim && state.italic && state.blink && state.rapid_blink);
+    assert!(state.inverted && state.hidden && state.strikethrough && state.overline);
+    assert_eq!(state.underline, Underline::Single);
+}
+
+#[test]
+fn reset_all() {
+    let console = console(4, 1, b"\x1B[1;2;3;4;5;6;7;8;9;53;31;42;58;5;1m\x1B[0m");
+    let state = &console.state;
+    assert!(! (state.bold || state.dim || state.italic || state.blink || state.rapid_blink));
+    assert!(! (state.inverted || state.hidden || state.strikethrough || state.overline));
+    assert_eq!(state.underline, Underline::None);
+    assert!(state.underline_color.is_none());
+    assert_eq!(state.foreground.as_rgb(), state.foreground_default.as_rgb());
+    assert_eq!(state.background.as_rgb(), state.background_default.as_rgb());
+}
+
+#[test]
+fn reset_without_parameters() {
+    let console = console(4, 1, b"\x1B[3;9m\x1B[m");
+    assert!(! console.state.italic);
+    assert!(! console.state.strikethrough);
+}
+
+#[test]
+fn reset_individually() {
+    let mut console = console(4, 1, b"\x1B[1;2;5;6;8;53m\x1B[22;25m");
+    let state = &console.state;
+    assert!(! (state.bold || state.dim || state.blink || state.rapid_blink));
+    assert!(state.hidden && state.overline);
+    console.write(b"\x1B[28;55;21m", |_| ());
+    let state = &console.state;
+    assert!(! (state.hidden || state.overline));
+    assert_eq!(state.underline, Underline::Double);
+}
+
+#[test]
+fn attributes_on_cells() {
+    let console = console(4, 1, b"\x1B[2;8;53ma\x1B[0mb");
+    let cell = console.screen().cell(0, 0).unwrap();
+    assert!(cell.dim && cell.hidden && cell.overline);
+    let cell = console.screen().cell(1, 0).unwrap();
+    assert!(! (cell.dim || cell.hidden || cell.overline));
+}
+
+fn true_color(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::TrueColor(r, g, b) => Some((r, g, b)),
+        Color::Ansi(_) => None,
+    }
+}
+
+fn ansi(color: Color) -> Option<u8> {
+    match color {
+        Color::Ansi(value) => Some(value),
+        Color::TrueColor(..) => None,
+    }
+}
+
+#[test]
+fn true_color_subparameters() {
+    let console = console(4, 1, b"\x1B[38:2::1:2:3;48:2:4:5:6;4m");
+    assert_eq!(true_color(console.state.foreground), Some((1, 2, 3)));
+    assert_eq!(true_color(console.state.background), Some((4, 5, 6)));
+    // The following parameter is not taken as part of the color
+    assert_eq!(console.state.underline, Underline::Single);
+}
+
+#[test]
+fn true_color_parameters() {
+    let console = console(4, 1, b"\x1B[38;2;1;2;3;48;5;200;3m");
+    assert_eq!(true_color(console.state.foreground), Some((1, 2, 3)));
+    assert_eq!(ansi(console.state.background), Some(200));
+    assert!(console.state.italic);
+}
+
+#[test]
+fn indexed_color_subparameters() {
+    let console = console(4, 1, b"\x1B[38:5:100;3m");
+    assert_eq!(ansi(console.state.foreground), Some(100));
+    assert!(console.state.italic);
+}
+
+#[test]
+fn underline_styles() {
+    let mut console = console(4, 1, b"\x1B[4:3m");
+    assert_eq!(console.state.underline, Underline::Curly);
+    console.write(b"\x1B[4:4m", |_| ());
+    assert_eq!(console.state.underline, Underline::Dotted);
+    console.write(b"\x1B[4:5m", |_| ());
+    assert_eq!(console.state.underline, Underline::Dashed);
+    console.write(b"\x1B[4:2m", |_| ());
+    assert_eq!(console.state.underline, Underline::Double);
+    console.write(b"\x1B[4:0m", |_| ());
+    assert_eq!(console.state.underline, Underline::None);
+    console.write(b"\x1B[4m", |_| ());
+    assert_eq!(console.state.underline, Underline::Single);
+}
+
+#[test]
+fn underline_color() {
+    let mut console = console(4, 1, b"\x1B[58:5:9m");
+    assert_eq!(console.state.underline_color.and_then(ansi), Some(9));
+    console.write(b"\x1B[58:2::10:20:30mx", |_| ());
+    assert_eq!(console.state.underline_color.and_then(true_color), Some((10, 20, 30)));
+    assert_eq!(console.screen().cell(0, 0).unwrap().underline_color.and_then(true_color), Some((10, 20, 30)));
+    console.write(b"\x1B[59m", |_| ());
+    assert!(console.state.underline_color.is_none());
+}
+
+#[test]
+fn bright_colors() {
+    let mut console = console(4, 1, b"\x1B[90;107m");
+    assert_eq!(ansi(console.state.foreground), Some(8));
+    assert_eq!(ansi(console.state.background), Some(15));
+    console.write(b"\x1B[97;100m", |_| ());
+    assert_eq!(ansi(console.state.foreground), Some(15));
+    assert_eq!(ansi(console.state.background), Some(8));
+}
+
+#[test]
+fn csi_entry_points() {
+    let mut console = console(4, 1, b"");
+    console.state.csi('m', &[38, 2, 1, 2, 3], b"", &mut |_| ());
+    assert_eq!(true_color(console.state.foreground), Some((1, 2, 3)));
+    console.state.csi_subparams('m', &[&[38, 2, 0, 4, 5, 6], &[4, 3]], b"", &mut |_| ());
+    assert_eq!(true_color(console.state.foreground), Some((4, 5, 6)));
+    assert_eq!(console.state.underline, Underline::Curly);
+}
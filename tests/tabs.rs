@@ -0,0 +1,56 @@
+extern crate ransid;
+
+mod common;
+
+use common::{console, input};
+
+#[test]
+fn default_tab_stops() {
+    let mut console = console(30, 1, b"");
+    assert_eq!(input(&mut console, b"\x1B[2$w"), "\x1BP2$u9/17/25\x1B\\");
+    console.write(b"\t", |_| ());
+    assert_eq!(console.state.x, 8);
+    console.write(b"\t\t\t", |_| ());
+    assert_eq!(console.state.x, 29);
+}
+
+#[test]
+fn set_and_clear_tab_stops() {
+    let mut console = console(30, 1, b"\x1B[5G\x1BH\x1B[12G\x1BH\x1B[9G\x1B[g");
+    assert_eq!(input(&mut console, b"\x1B[2$w"), "\x1BP2$u5/12/17/25\x1B\\");
+    console.write(b"\x1B[3g\x1B[5G\x1BH", |_| ());
+    assert_eq!(input(&mut console, b"\x1B[2$w"), "\x1BP2$u5\x1B\\");
+    console.write(b"\r\t", |_| ());
+    assert_eq!(console.state.x, 4);
+}
+
+#[test]
+fn forward_and_backward_tabulation() {
+    let mut console = console(30, 1, b"\x1B[2I");
+    assert_eq!(console.state.x, 16);
+    console.write(b"\x1B[Z", |_| ());
+    assert_eq!(console.state.x, 8);
+    console.write(b"\x1B[3G\x1B[Z", |_| ());
+    assert_eq!(console.state.x, 0);
+    console.write(b"\x1B[9I", |_| ());
+    assert_eq!(console.state.x, 29);
+    console.write(b"\x1B[9Z", |_| ());
+    assert_eq!(console.state.x, 0);
+}
+
+#[test]
+fn reset_tab_stops() {
+    let mut console = console(30, 1, b"\x1B[3g\x1B[?5W");
+    assert_eq!(input(&mut console, b"\x1B[2$w"), "\x1BP2$u9/17/25\x1B\\");
+    console.write(b"\x1B[3g\x1Bc", |_| ());
+    assert_eq!(input(&mut console, b"\x1B[2$w"), "\x1BP2$u9/17/25\x1B\\");
+}
+
+#[test]
+fn resize_keeps_tab_stops() {
+    let mut console = console(20, 1, b"\x1B[3g\x1B[4G\x1BH\x1B[15G\x1BH");
+    console.resize(10, 1);
+    assert_eq!(input(&mut console, b"\x1B[2$w"), "\x1BP2$u4\x1B\\");
+    console.resize(30, 1);
+    assert_eq!(input(&mut console, b"\x1B[2$w"), "\x1BP2$u4/17/25\x1B\\");
+}
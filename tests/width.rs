@@ -0,0 +1,67 @@
+extern crate ransid;
+
+mod common;
+
+use ransid::Console;
+use common::rows;
+
+fn console(ambiguous: usize, vs16_wide: bool, w: usize, h: usize, data: &str) -> Console {
+    let mut console = Console::new(w, h);
+    console.state.width_policy.ambiguous = ambiguous;
+    console.state.width_policy.vs16_wide = vs16_wide;
+    console.write(data.as_bytes(), |_| ());
+    console
+}
+
+#[test]
+fn ambiguous_narrow() {
+    let console = console(1, false, 4, 1, "§±");
+    assert_eq!(rows(&console), ["§±  "]);
+    assert_eq!(console.state.x, 2);
+}
+
+#[test]
+fn ambiguous_wide() {
+    let console = console(2, false, 4, 1, "§±");
+    assert_eq!(rows(&console), ["§ ± "]);
+    assert!(console.screen().cell(0, 0).unwrap().wide);
+    assert_eq!(console.state.x, 3);
+    assert!(console.state.pending_wrap);
+}
+
+#[test]
+fn ambiguous_wide_wraps() {
+    let console = console(2, false, 4, 2, "ab§±");
+    assert_eq!(rows(&console), ["ab§ ", "±   "]);
+}
+
+#[test]
+fn vs16_narrow() {
+    let console = console(1, false, 4, 1, "\u{2764}\u{FE0F}x");
+    assert_eq!(rows(&console), ["\u{2764}x  "]);
+    assert!(! console.screen().cell(0, 0).unwrap().wide);
+}
+
+#[test]
+fn vs16_wide() {
+    let console = console(1, true, 4, 1, "\u{2764}\u{FE0F}x");
+    assert_eq!(rows(&console), ["\u{2764} x "]);
+    assert!(console.screen().cell(0, 0).unwrap().wide);
+    assert_eq!(console.state.x, 3);
+}
+
+#[test]
+fn vs16_wide_last_column() {
+    let console = console(1, true, 2, 2, "a\u{2764}\u{FE0F}x");
+    assert_eq!(rows(&console), ["a\u{2764}", "x "]);
+    assert!(! console.screen().cell(1, 0).unwrap().wide);
+}
+
+#[test]
+fn overrides() {
+    let mut console = Console::new(4, 1);
+    console.state.width_policy.overrides.push(('\u{E000}', '\u{F8FF}', 2));
+    console.write("\u{E000}a".as_bytes(), |_| ());
+    assert_eq!(rows(&console), ["\u{E000} a "]);
+    assert_eq!(console.state.x, 3);
+}
@@ -0,0 +1,83 @@
+extern crate ransid;
+
+mod common;
+
+use common::{console, input, rows};
+
+#[test]
+fn pending_wrap() {
+    let console = console(4, 2, b"abcd");
+    assert_eq!((console.state.x, console.state.y), (3, 0));
+    assert!(console.state.pending_wrap);
+}
+
+#[test]
+fn full_width_line() {
+    let console = console(4, 3, b"abcd\r\nef");
+    assert_eq!(rows(&console), ["abcd", "ef  ", "    "]);
+    assert_eq!((console.state.x, console.state.y), (2, 1));
+}
+
+#[test]
+fn wrap_after_attributes() {
+    let console = console(4, 2, b"abcd\x1B[31mX");
+    assert_eq!(rows(&console), ["abcd", "X   "]);
+}
+
+#[test]
+fn backspace() {
+    let console = console(4, 2, b"abcd\x08X");
+    assert_eq!(rows(&console), ["abXd", "    "]);
+    assert!(! console.state.pending_wrap);
+}
+
+#[test]
+fn cursor_position_report() {
+    let mut console = console(4, 2, b"abcd");
+    assert_eq!(input(&mut console, b"\x1B[6n"), "\x1B[1;4R");
+    assert!(console.state.pending_wrap);
+}
+
+#[test]
+fn save_and_restore_cursor() {
+    let console = console(4, 3, b"abcd\x1B7\x1B[3;1H\x1B8X");
+    assert_eq!(rows(&console), ["abcd", "X   ", "    "]);
+}
+
+#[test]
+fn cursor_movement_cancels() {
+    let console = console(4, 2, b"abcd\x1B[1GX");
+    assert_eq!(rows(&console), ["Xbcd", "    "]);
+}
+
+#[test]
+fn tab_cancels() {
+    let console = console(4, 2, b"abcd\tX");
+    assert_eq!(rows(&console), ["abcX", "    "]);
+}
+
+#[test]
+fn forward_tabulation_cancels() {
+    let console = console(4, 2, b"abcd\x1B[IX");
+    assert_eq!(rows(&console), ["abcX", "    "]);
+}
+
+#[test]
+fn erase_character_cancels() {
+    let console = console(4, 2, b"abcd\x1B[XZ");
+    assert_eq!(rows(&console), ["abcZ", "    "]);
+}
+
+#[test]
+fn no_autowrap() {
+    let console = console(4, 2, b"\x1B[?7labcdXY");
+    assert_eq!(rows(&console), ["abcY", "    "]);
+    assert_eq!((console.state.x, console.state.y), (3, 0));
+    assert!(! console.state.pending_wrap);
+}
+
+#[test]
+fn wide_character() {
+    let console = console(4, 2, "ab中X".as_bytes());
+    assert_eq!(rows(&console), ["ab中 ", "X   "]);
+}